@@ -0,0 +1,456 @@
+//! Pluggable POA aligner backends.
+//!
+//! Each supported tool (POASTA, SPOA, abPOA) implements `AlignerBackend`. Adding a new aligner
+//! means writing an impl of this trait and adding it to `registry()` -- nothing in the worker's
+//! dispatch logic needs to change.
+
+use std::any::Any;
+use std::fs::File;
+use std::io::{BufReader, Write};
+use std::path::Path;
+use std::time::Duration;
+
+use flate2::read::GzDecoder;
+use noodles::fasta;
+
+use poasta::aligner::astar::heuristic::Dijkstra;
+use poasta::aligner::astar::AlignableGraph;
+use poasta::aligner::cost_models::affine::Affine;
+use poasta::aligner::{AlignmentMode, GraphAligner, PoastaAligner};
+use poasta::graph::io::dot::graph_to_dot;
+use poasta::graph::poa::POASeqGraph;
+
+use crate::bench;
+use crate::bench::MeasureOptions;
+use crate::dataset::Dataset;
+use crate::errors::POABenchError;
+use crate::ipc;
+use crate::jobs::{Algorithm, JobResult};
+
+/// Graph state produced by `AlignerBackend::build_graph`. Each backend downcasts this back to
+/// its own concrete graph type in `align_single`.
+pub type BoxedGraph = Box<dyn Any>;
+
+/// A pluggable POA aligner tool, wiring a single `(build_graph, align_single, run_full_msa)`
+/// trio into the worker in place of what used to be a hard-coded match per tool.
+pub trait AlignerBackend {
+    /// Name used on the command line (see `Algorithm::to_str()`) to select this backend.
+    fn name(&self) -> &'static str;
+
+    /// How long to let the machine settle after `build_graph` before measuring alignments.
+    /// Some libraries (SPOA, abPOA) leave background threads winding down right after graph
+    /// construction that would otherwise pollute the first measurement.
+    fn settle_delay(&self) -> Duration {
+        Duration::ZERO
+    }
+
+    /// Builds (or loads) the POA graph used for single-sequence alignment benchmarks.
+    fn build_graph(&self, dataset: &Dataset, output_dir: &Path) -> Result<BoxedGraph, POABenchError>;
+
+    /// Aligns each of `sequences` against `graph`, sending one `SingleSeqMeasurement` per
+    /// sequence, measured according to `options` (see `bench::MeasureOptions`). Backends that
+    /// support verification double-check each reported score before sending it (see
+    /// `verify::verify_poasta_traceback`).
+    fn align_single(
+        &self,
+        dataset: &Dataset,
+        graph: &mut BoxedGraph,
+        sequences: &[fasta::Record],
+        writer: &mut dyn Write,
+        result_fd: Option<i32>,
+        options: MeasureOptions,
+    ) -> Result<(), POABenchError>;
+
+    /// Builds the graph incrementally from `sequences` (the full-MSA benchmark), sending a
+    /// `FullMSAMeasurement` once done, measured according to `options`. When `options.repeats` is
+    /// more than 1 the graph is rebuilt from scratch on every repeat, since it is mutated in
+    /// place as sequences are added.
+    fn run_full_msa(
+        &self,
+        dataset: &Dataset,
+        output_dir: &Path,
+        sequences: &[fasta::Record],
+        writer: &mut dyn Write,
+        result_fd: Option<i32>,
+        options: MeasureOptions,
+    ) -> Result<(), POABenchError>;
+}
+
+/// All backends known to this build. Register a new aligner here.
+pub fn registry() -> Vec<Box<dyn AlignerBackend>> {
+    vec![
+        Box::new(PoastaBackend),
+        Box::new(SpoaBackend),
+        Box::new(AbpoaBackend),
+    ]
+}
+
+/// Looks up the backend for `algorithm` by name. Panics if no backend is registered for it,
+/// which would mean `Algorithm` and `registry()` have drifted out of sync.
+pub fn backend_for(algorithm: Algorithm) -> Box<dyn AlignerBackend> {
+    registry().into_iter()
+        .find(|backend| backend.name() == algorithm.to_str())
+        .unwrap_or_else(|| panic!("No aligner backend registered for {:?}", algorithm))
+}
+
+pub struct PoastaBackend;
+
+impl AlignerBackend for PoastaBackend {
+    fn name(&self) -> &'static str {
+        "poasta"
+    }
+
+    fn build_graph(&self, dataset: &Dataset, output_dir: &Path) -> Result<BoxedGraph, POABenchError> {
+        let mut msa_file = File::open(dataset.graph_msa_fname(output_dir))
+            .map(BufReader::new)?;
+
+        let graph = POASeqGraph::<u32>::try_from_fasta_msa(&mut msa_file)?;
+
+        Ok(Box::new(graph))
+    }
+
+    fn align_single(
+        &self, dataset: &Dataset, graph: &mut BoxedGraph, sequences: &[fasta::Record],
+        writer: &mut dyn Write, result_fd: Option<i32>, options: MeasureOptions
+    ) -> Result<(), POABenchError> {
+        let graph = graph.downcast_ref::<POASeqGraph<u32>>()
+            .expect("PoastaBackend::build_graph did not return a POASeqGraph<u32>");
+
+        let cost_model = Affine::new(4, 6, 2);
+        let aligner = PoastaAligner::<Dijkstra, i32, u32, _, _>::new(cost_model);
+
+        let memory_start = bench::get_maxrss();
+        let graph_node_count = graph.node_count();
+
+        for seq in sequences {
+            let replicates = bench::measure_replicates(
+                memory_start, bench::DEFAULT_FREQ_TOLERANCE, &options, || {
+                    let result = aligner
+                        .align(graph, seq.sequence().as_ref(), AlignmentMode::Global).unwrap();
+
+                    (result.score.as_usize(), result.num_visited, result.alignment)
+                })?;
+
+            for (replicate, (measured, (score, num_visited, alignment))) in replicates.into_iter().enumerate() {
+                if options.verify {
+                    crate::verify::verify_poasta_traceback(
+                        graph, seq.sequence().as_ref(), &alignment, &cost_model, score
+                    )?;
+                }
+
+                let result = JobResult::SingleSeqMeasurement(
+                    Algorithm::POASTA,
+                    dataset.name().to_string(),
+                    score,
+                    graph_node_count,
+                    0,
+                    unsafe { String::from_utf8_unchecked(seq.name().to_vec()) },
+                    seq.sequence().len(),
+                    num_visited,
+                    measured,
+                    replicate
+                );
+
+                ipc::send_result(writer, result_fd, &result)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn run_full_msa(
+        &self, dataset: &Dataset, output_dir: &Path, sequences: &[fasta::Record],
+        writer: &mut dyn Write, result_fd: Option<i32>, options: MeasureOptions
+    ) -> Result<(), POABenchError> {
+        let aligner = PoastaAligner::<Dijkstra, i32, u32, _, _>::new(Affine::new(4, 6, 2));
+
+        let memory_start = bench::get_maxrss();
+
+        // Rebuilt from scratch every replicate, since the graph is mutated in place as sequences
+        // are added -- only the graph from the final replicate is kept around to save to disk.
+        let replicates = bench::measure_replicates(
+            memory_start, bench::DEFAULT_FREQ_TOLERANCE, &options,
+            || -> (usize, (Result<(), POABenchError>, POASeqGraph<u32>)) {
+                let mut graph = POASeqGraph::<u32>::new();
+
+                let build = (|| -> Result<(), POABenchError> {
+                    for (i, seq) in sequences.iter().enumerate() {
+                        let weights: Vec<usize> = vec![1; seq.sequence().len()];
+
+                        let seq_name = unsafe { std::str::from_utf8_unchecked(seq.name()) };
+                        if graph.is_empty() {
+                            graph.add_aligned_sequence(seq_name, seq.sequence(), &weights, None)?;
+                        } else {
+                            let result = aligner
+                                .align(&graph, seq.sequence(), AlignmentMode::Global)?;
+
+                            graph.add_aligned_sequence(seq_name, seq.sequence(), &weights, Some(&result.alignment))?;
+                            eprintln!("Aligned #{} {} with score {}", i+1, seq_name, result.score.as_usize());
+                        }
+                    }
+
+                    Ok(())
+                })();
+
+                (0, (build, graph))
+            })?;
+
+        let mut last_graph = None;
+        for (replicate, (measured, (build_result, graph))) in replicates.into_iter().enumerate() {
+            build_result?;
+
+            let result = JobResult::FullMSAMeasurement(
+                Algorithm::POASTA,
+                dataset.name().to_string(),
+                measured,
+                replicate
+            );
+
+            ipc::send_result(writer, result_fd, &result)?;
+            last_graph = Some(graph);
+        }
+
+        // Save the final replicate's graph to file
+        let mut graph_outfile = File::create(dataset.poasta_msa_output(output_dir))?;
+        graph_to_dot(&mut graph_outfile, &last_graph.expect("at least one replicate always runs"))?;
+
+        Ok(())
+    }
+}
+
+pub struct SpoaBackend;
+
+impl AlignerBackend for SpoaBackend {
+    fn name(&self) -> &'static str {
+        "spoa"
+    }
+
+    fn settle_delay(&self) -> Duration {
+        Duration::from_secs(1)
+    }
+
+    fn build_graph(&self, dataset: &Dataset, _output_dir: &Path) -> Result<BoxedGraph, POABenchError> {
+        eprintln!("Preparing SPOA graph for {:?}...", dataset.name());
+        let Some(graph_seq_fname) = dataset.graph_sequences_fname() else {
+            return Err(POABenchError::BuildGraphError(String::from("No graph sequence set filename")))
+        };
+
+        let mut reader = File::open(graph_seq_fname)
+            .map(GzDecoder::new)
+            .map(BufReader::new)
+            .map(fasta::Reader::new)?;
+
+        let mut graph = spoa_rs::Graph::new();
+        let mut engine = spoa_rs::AlignmentEngine::new_affine(spoa_rs::AlignmentType::kNW, 0, -4, -8, -2);
+
+        for record in reader.records() {
+            let r = record?;
+
+            let seq = std::str::from_utf8(r.sequence().as_ref())?;
+            let (_, aln) = engine.align(seq, &graph);
+
+            graph.add_alignment(aln, seq);
+        }
+
+        drop(engine);
+
+        Ok(Box::new(graph))
+    }
+
+    fn align_single(
+        &self, dataset: &Dataset, graph: &mut BoxedGraph, sequences: &[fasta::Record],
+        writer: &mut dyn Write, result_fd: Option<i32>, options: MeasureOptions
+    ) -> Result<(), POABenchError> {
+        let graph = graph.downcast_ref::<spoa_rs::Graph>()
+            .expect("SpoaBackend::build_graph did not return a spoa_rs::Graph");
+
+        eprintln!("Performing alignments with SPOA for {:?}...", dataset.name());
+        bench::reset_max_rss()?;
+        let memory_start = bench::get_maxrss();
+        let graph_node_count = graph.node_count();
+        let graph_edge_count = graph.edge_count();
+
+        let mut aligner = spoa_rs::AlignmentEngine::new_affine(spoa_rs::AlignmentType::kNW, 0, -4, -8, -2);
+
+        for seq in sequences {
+            let sequence = std::str::from_utf8(seq.sequence().as_ref())?;
+            let replicates = bench::measure_replicates(
+                memory_start, bench::DEFAULT_FREQ_TOLERANCE, &options, || {
+                    let (score, alignment) = aligner.align(sequence, graph);
+
+                    ((-score) as usize, alignment)
+                })?;
+
+            let num_visited = (sequence.len() + 1) * (graph_node_count + 1) * 3;
+
+            for (replicate, (measured, (score, _))) in replicates.into_iter().enumerate() {
+                let result = JobResult::SingleSeqMeasurement(
+                    Algorithm::SPOA,
+                    dataset.name().to_string(),
+                    score,
+                    graph_node_count,
+                    graph_edge_count,
+                    unsafe { String::from_utf8_unchecked(seq.name().to_vec()) },
+                    seq.sequence().len(),
+                    num_visited,
+                    measured,
+                    replicate
+                );
+
+                ipc::send_result(writer, result_fd, &result)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn run_full_msa(
+        &self, dataset: &Dataset, _output_dir: &Path, sequences: &[fasta::Record],
+        writer: &mut dyn Write, result_fd: Option<i32>, options: MeasureOptions
+    ) -> Result<(), POABenchError> {
+        let memory_start = bench::get_maxrss();
+
+        let replicates = bench::measure_replicates(
+            memory_start, bench::DEFAULT_FREQ_TOLERANCE, &options, || {
+                let mut graph = spoa_rs::Graph::new();
+                let mut engine = spoa_rs::AlignmentEngine::new_affine(spoa_rs::AlignmentType::kNW, 0, -4, -8, -2);
+
+                for record in sequences {
+                    let seq = unsafe { std::str::from_utf8_unchecked(record.sequence().as_ref()) };
+                    let (_, aln) = engine.align(seq, &graph);
+
+                    graph.add_alignment(aln, seq);
+                }
+
+                (0, ())
+            })?;
+
+        for (replicate, (measured, _)) in replicates.into_iter().enumerate() {
+            let result = JobResult::FullMSAMeasurement(
+                Algorithm::SPOA,
+                dataset.name().to_string(),
+                measured,
+                replicate
+            );
+
+            ipc::send_result(writer, result_fd, &result)?;
+        }
+
+        Ok(())
+    }
+}
+
+pub struct AbpoaBackend;
+
+impl AlignerBackend for AbpoaBackend {
+    fn name(&self) -> &'static str {
+        "abpoa"
+    }
+
+    fn settle_delay(&self) -> Duration {
+        Duration::from_secs(1)
+    }
+
+    fn build_graph(&self, dataset: &Dataset, output_dir: &Path) -> Result<BoxedGraph, POABenchError> {
+        eprintln!("Loading abPOA graph for {:?}...", dataset.name());
+        let graph_seq_fname = dataset.graph_msa_fname(output_dir);
+
+        Ok(Box::new(abpoa_rs::Graph::from_file(&graph_seq_fname, false)))
+    }
+
+    fn align_single(
+        &self, dataset: &Dataset, graph: &mut BoxedGraph, sequences: &[fasta::Record],
+        writer: &mut dyn Write, result_fd: Option<i32>, options: MeasureOptions
+    ) -> Result<(), POABenchError> {
+        let graph = graph.downcast_mut::<abpoa_rs::Graph>()
+            .expect("AbpoaBackend::build_graph did not return an abpoa_rs::Graph");
+
+        eprintln!("Performing alignments with abPOA for {:?}...", dataset.name());
+        bench::reset_max_rss()?;
+
+        let memory_start = bench::get_maxrss();
+        let graph_node_count = graph.num_nodes() - 2;
+        let graph_edge_count = 0;
+
+        let aln_params = abpoa_rs::AlignmentParametersBuilder::new()
+            .alignment_mode(abpoa_rs::AlignmentMode::Global)
+            .gap_affine_penalties(0, 4, 6, 2)
+            .verbosity(abpoa_rs::Verbosity::None)
+            .build();
+
+        for seq in sequences {
+            let replicates = bench::measure_replicates(
+                memory_start, bench::DEFAULT_FREQ_TOLERANCE, &options, || {
+                    let result = graph.align_sequence(
+                        &aln_params,
+                        seq.sequence().as_ref(),
+                    ).unwrap();
+
+                    (-result.get_best_score() as usize, result)
+                })?;
+
+            for (replicate, (measured, (score, _))) in replicates.into_iter().enumerate() {
+                let result = JobResult::SingleSeqMeasurement(
+                    Algorithm::abPOA,
+                    dataset.name().to_string(),
+                    score,
+                    graph_node_count,
+                    graph_edge_count,
+                    unsafe { String::from_utf8_unchecked(seq.name().to_vec()) },
+                    seq.sequence().len(),
+                    0,
+                    measured,
+                    replicate
+                );
+
+                ipc::send_result(writer, result_fd, &result)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn run_full_msa(
+        &self, dataset: &Dataset, _output_dir: &Path, sequences: &[fasta::Record],
+        writer: &mut dyn Write, result_fd: Option<i32>, options: MeasureOptions
+    ) -> Result<(), POABenchError> {
+        let seqs: Vec<_> = sequences.iter()
+            .map(|s| s.sequence().as_ref())
+            .collect();
+        let weights: Vec<_> = sequences.iter()
+            .map(|s| vec![1; s.sequence().len()])
+            .collect();
+        let names: Vec<_> = sequences.iter()
+            .map(|s| s.name())
+            .collect();
+
+        let memory_start = bench::get_maxrss();
+
+        let aln_params = abpoa_rs::AlignmentParametersBuilder::new()
+            .alignment_mode(abpoa_rs::AlignmentMode::Global)
+            .gap_affine_penalties(0, 4, 6, 2)
+            .verbosity(abpoa_rs::Verbosity::None)
+            .build();
+
+        let replicates = bench::measure_replicates(
+            memory_start, bench::DEFAULT_FREQ_TOLERANCE, &options, || {
+                let mut graph = abpoa_rs::Graph::new(&aln_params);
+                let _ = graph.align_and_add_multiple(&aln_params, &seqs, &weights, &names).unwrap();
+
+                (0, ())
+            })?;
+
+        for (replicate, (measured, _)) in replicates.into_iter().enumerate() {
+            let result = JobResult::FullMSAMeasurement(
+                Algorithm::abPOA,
+                dataset.name().to_string(),
+                measured,
+                replicate
+            );
+
+            ipc::send_result(writer, result_fd, &result)?;
+        }
+
+        Ok(())
+    }
+}
@@ -6,6 +6,11 @@
 use std::{path::Path, process, time::Instant};
 use std::fs::File;
 use std::io::Write;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
 
 use chrono::SubsecRound;
 use libc;
@@ -15,11 +20,41 @@ use crate::errors::POABenchError;
 
 pub type Bytes = u64;
 
+/// Default fraction the CPU frequency is allowed to drift between the start and end of a
+/// measurement before the run is flagged as unreliable.
+pub const DEFAULT_FREQ_TOLERANCE: f32 = 0.1;
+
+/// Controls how a single sequence (or the full MSA) is measured: whether to double-check the
+/// reported score, whether to record a resource usage time series, and how many replicate
+/// measurements to take. Replicates are each reported individually (tagged with their index, see
+/// `JobResult`) rather than aggregated in-process, so the orchestrator can compute distribution
+/// statistics across the whole fleet of workers instead of just one.
+#[derive(Debug, Clone, Copy)]
+pub struct MeasureOptions {
+    /// Recompute each alignment's reported score independently (currently only supported for
+    /// POASTA) and fail loudly on any mismatch.
+    pub verify: bool,
+    /// When set, poll resource usage at this interval for the duration of each measurement (see
+    /// `Measured::samples`).
+    pub sample_interval: Option<Duration>,
+    /// Number of untimed replicates to run before the timed ones, to let caches/allocators warm up.
+    pub warmup: usize,
+    /// Number of timed replicates to run and report. At least one is always run, even if
+    /// `repeats` is 0.
+    pub repeats: usize,
+}
+
+impl Default for MeasureOptions {
+    fn default() -> Self {
+        MeasureOptions { verify: false, sample_interval: None, warmup: 0, repeats: 1 }
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Measured {
     /// Alignment score
     pub score: usize,
-    /// Runtime in seconds.
+    /// Runtime in seconds for this replicate.
     pub runtime: f32,
     /// max_rss after reading input file.
     pub memory_initial: Option<Bytes>,
@@ -36,10 +71,34 @@ pub struct Measured {
     /// Cpu frequency at start/end.
     pub cpu_freq_start: Option<f32>,
     pub cpu_freq_end: Option<f32>,
+    /// Set when the thread migrated cores mid-measurement, or the CPU frequency drifted by more
+    /// than the configured tolerance, either of which means `runtime` is likely noisy.
+    pub unreliable: bool,
+    /// Resource usage sampled at regular intervals while `f` was running, oldest first. Empty
+    /// unless a `sample_interval` was passed to `measure`.
+    pub samples: Vec<ResourceSample>,
 }
 
-/// F can return some state that is dropped only after the memory is measured.
-pub fn measure<F: FnOnce() -> usize>(memory_start: Bytes, f: F) -> Result<Measured, POABenchError> {
+/// A single point of the resource usage time series recorded during `measure`, when sampling is
+/// enabled. `elapsed_ms` is relative to the start of the measured closure.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ResourceSample {
+    pub elapsed_ms: u64,
+    pub rss: Bytes,
+    pub cpu: Option<i32>,
+    pub cpu_freq: Option<f32>,
+}
+
+/// F returns the alignment score plus some state (e.g. the traceback) that is only dropped
+/// after the memory is measured, and is handed back to the caller alongside `Measured`.
+///
+/// When `sample_interval` is `Some`, a background thread polls `/proc/self/statm` (and the
+/// current CPU/frequency) at that interval for the duration of `f`, recording a resource usage
+/// time series in `Measured::samples`. One final sample is taken synchronously right after `f`
+/// returns, while its returned state is still alive, so a teardown-time peak isn't missed.
+pub fn measure<T, F: FnOnce() -> (usize, T)>(
+    memory_start: Bytes, freq_tolerance: f32, sample_interval: Option<Duration>, f: F
+) -> Result<(Measured, T), POABenchError> {
     reset_max_rss()?;
 
     let cpu_start = get_cpu();
@@ -47,7 +106,35 @@ pub fn measure<F: FnOnce() -> usize>(memory_start: Bytes, f: F) -> Result<Measur
     let time_start = chrono::Utc::now().trunc_subsecs(3);
     let start = Instant::now();
 
-    let score = f();
+    let sampler = sample_interval.map(|interval| {
+        let stop = Arc::new(AtomicBool::new(false));
+        let stop_for_thread = Arc::clone(&stop);
+        let (tx, rx) = mpsc::channel();
+        let tx_for_thread = tx.clone();
+
+        let handle = thread::spawn(move || {
+            while !stop_for_thread.load(Ordering::Relaxed) {
+                sample_resources(start, &tx_for_thread);
+                thread::sleep(interval);
+            }
+        });
+
+        (stop, tx, rx, handle)
+    });
+
+    let (score, state) = f();
+
+    let samples = match sampler {
+        Some((stop, tx, rx, handle)) => {
+            sample_resources(start, &tx);
+            stop.store(true, Ordering::Relaxed);
+            drop(tx);
+            handle.join().expect("resource sampler thread panicked");
+
+            rx.try_iter().collect()
+        },
+        None => Vec::new(),
+    };
 
     let runtime = start.elapsed().as_secs_f32();
     let time_end = chrono::Utc::now().trunc_subsecs(3);
@@ -56,7 +143,15 @@ pub fn measure<F: FnOnce() -> usize>(memory_start: Bytes, f: F) -> Result<Measur
     let cpu_end = get_cpu();
     let cpu_freq_end = cpu_end.and_then(|c| get_cpu_freq(c));
 
-    Ok(Measured {
+    let migrated = cpu_start != cpu_end;
+    let freq_drifted = match (cpu_freq_start, cpu_freq_end) {
+        (Some(start_freq), Some(end_freq)) if start_freq > 0.0 => {
+            ((end_freq - start_freq).abs() / start_freq) > freq_tolerance
+        },
+        _ => false,
+    };
+
+    Ok((Measured {
         score,
         runtime,
         memory_initial: Some(memory_start),
@@ -68,7 +163,105 @@ pub fn measure<F: FnOnce() -> usize>(memory_start: Bytes, f: F) -> Result<Measur
         cpu_end,
         cpu_freq_start,
         cpu_freq_end,
-    })
+        unreliable: migrated || freq_drifted,
+        samples,
+    }, state))
+}
+
+/// Runs `f` `options.warmup` times (discarded, unsampled) followed by `options.repeats.max(1)`
+/// timed replicates, returning every timed replicate's `Measured`/state pair in order.
+///
+/// `f` must rebuild any mutable state it needs from scratch on every call (e.g. a POA graph built
+/// incrementally for the full-MSA benchmark), since otherwise later replicates would not be
+/// measuring the same amount of work as the first.
+pub fn measure_replicates<T, F: FnMut() -> (usize, T)>(
+    memory_start: Bytes, freq_tolerance: f32, options: &MeasureOptions, mut f: F
+) -> Result<Vec<(Measured, T)>, POABenchError> {
+    for _ in 0..options.warmup {
+        measure(memory_start, freq_tolerance, None, || f())?;
+    }
+
+    (0..options.repeats.max(1))
+        .map(|_| measure(memory_start, freq_tolerance, options.sample_interval, || f()))
+        .collect()
+}
+
+/// Mean, median, sample standard deviation, MAD, min and max of a set of replicate measurements
+/// for a single metric, as computed by the orchestrator once all of a job's replicates have
+/// arrived.
+#[derive(Debug, Clone, Copy)]
+pub struct Stats {
+    pub mean: f64,
+    pub median: f64,
+    /// Sample standard deviation (unbiased, n-1 divisor). Zero when there's only one replicate.
+    pub stddev: f64,
+    /// Median absolute deviation from the median: a dispersion estimate that, unlike `stddev`,
+    /// isn't dragged around by the occasional outlier replicate (a stalled scheduler tick, a page
+    /// fault mid-run). Zero when there's only one replicate.
+    pub mad: f64,
+    pub min: f64,
+    pub max: f64,
+}
+
+/// Computes `Stats` over `values`, or `None` if `values` is empty.
+pub fn compute_stats(values: &[f64]) -> Option<Stats> {
+    if values.is_empty() {
+        return None;
+    }
+
+    let n = values.len();
+    let mut sorted = values.to_vec();
+    sorted.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+    let mean = sorted.iter().sum::<f64>() / n as f64;
+    let median = if n % 2 == 0 {
+        (sorted[n / 2 - 1] + sorted[n / 2]) / 2.0
+    } else {
+        sorted[n / 2]
+    };
+
+    let stddev = if n > 1 {
+        let variance = sorted.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / (n - 1) as f64;
+        variance.sqrt()
+    } else {
+        0.0
+    };
+
+    let mut abs_deviations: Vec<f64> = sorted.iter().map(|v| (v - median).abs()).collect();
+    abs_deviations.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    let mad = if n % 2 == 0 {
+        (abs_deviations[n / 2 - 1] + abs_deviations[n / 2]) / 2.0
+    } else {
+        abs_deviations[n / 2]
+    };
+
+    Some(Stats { mean, median, stddev, mad, min: sorted[0], max: sorted[n - 1] })
+}
+
+/// Takes one resource usage sample and sends it down `tx`, ignoring send errors (the receiver
+/// may already have stopped listening once the measurement completes).
+fn sample_resources(start: Instant, tx: &mpsc::Sender<ResourceSample>) {
+    let cpu = get_cpu();
+    let sample = ResourceSample {
+        elapsed_ms: start.elapsed().as_millis() as u64,
+        rss: current_rss(),
+        cpu,
+        cpu_freq: cpu.and_then(get_cpu_freq),
+    };
+
+    let _ = tx.send(sample);
+}
+
+/// Reads the process's current (not peak) resident set size from `/proc/self/statm`, in bytes.
+fn current_rss() -> Bytes {
+    let page_size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) } as Bytes;
+
+    std::fs::read_to_string("/proc/self/statm")
+        .ok()
+        .and_then(|contents| contents.split_whitespace().nth(1).map(str::to_string))
+        .and_then(|rss_pages| rss_pages.parse::<Bytes>().ok())
+        .map(|rss_pages| rss_pages * page_size)
+        .unwrap_or(0)
 }
 
 /// Returns the maximum resident set size, i.e. the physical memory the thread
@@ -126,4 +319,144 @@ pub fn reset_max_rss() -> Result<(), POABenchError> {
     {
         Err(POABenchError::MemoryResetError)
     }
+}
+
+/// Hardware and OS profile captured once per worker, so results from different machines can be
+/// told apart and reproduced.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SystemInfo {
+    pub cpu_model: String,
+    pub physical_cores: Option<usize>,
+    pub logical_cores: usize,
+    pub cache_l1: Option<Bytes>,
+    pub cache_l2: Option<Bytes>,
+    pub cache_l3: Option<Bytes>,
+    pub total_ram: Option<Bytes>,
+    pub cpu_governor: Option<String>,
+    pub turbo_enabled: Option<bool>,
+    pub os: String,
+}
+
+/// Captures a `SystemInfo` snapshot for the machine the worker is running on. Best-effort: any
+/// field we can't determine (e.g. on a non-Linux OS, or a missing sysfs entry) is left as `None`
+/// (or "unknown" for `cpu_model`/`os` and 0 for `logical_cores`, which aren't optional) rather
+/// than failing the whole profile.
+pub fn system_info() -> SystemInfo {
+    let (cpu_model, physical_cores, logical_cores) = cpuinfo_summary();
+
+    SystemInfo {
+        cpu_model,
+        physical_cores,
+        logical_cores,
+        cache_l1: cache_size(0).or_else(|| cache_size(1)),
+        cache_l2: cache_size(2),
+        cache_l3: cache_size(3),
+        total_ram: meminfo_total(),
+        cpu_governor: cpu_governor(),
+        turbo_enabled: turbo_enabled(),
+        os: os_release(),
+    }
+}
+
+/// Parses `/proc/cpuinfo`, returning the model name string, the number of physical cores
+/// reported by the first logical CPU, and the total number of logical CPUs.
+fn cpuinfo_summary() -> (String, Option<usize>, usize) {
+    let Ok(contents) = std::fs::read_to_string("/proc/cpuinfo") else {
+        return (String::from("unknown"), None, 0);
+    };
+
+    let mut model = None;
+    let mut physical_cores = None;
+    let mut logical_cores = 0;
+
+    for block in contents.split("\n\n") {
+        if block.trim().is_empty() {
+            continue;
+        }
+
+        logical_cores += 1;
+
+        for line in block.lines() {
+            let Some((key, value)) = line.split_once(':') else {
+                continue;
+            };
+
+            let key = key.trim();
+            let value = value.trim();
+
+            if key == "model name" && model.is_none() {
+                model = Some(value.to_string());
+            } else if key == "cpu cores" && physical_cores.is_none() {
+                physical_cores = value.parse().ok();
+            }
+        }
+    }
+
+    (model.unwrap_or_else(|| String::from("unknown")), physical_cores, logical_cores)
+}
+
+/// Reads the size in bytes of the first CPU's cache at the given index (0-3), as exposed under
+/// `/sys/devices/system/cpu/cpu0/cache/index{index}/`, filtering by cache level to distinguish
+/// L1 data/instruction caches (both index 0/1) from L2/L3.
+fn cache_size(index: u8) -> Option<Bytes> {
+    let base = format!("/sys/devices/system/cpu/cpu0/cache/index{index}");
+    let level = std::fs::read_to_string(format!("{base}/level")).ok()?
+        .trim().parse::<u8>().ok()?;
+
+    let expected_level = match index {
+        0 | 1 => 1,
+        2 => 2,
+        3 => 3,
+        _ => return None,
+    };
+
+    if level != expected_level {
+        return None;
+    }
+
+    let size = std::fs::read_to_string(format!("{base}/size")).ok()?;
+    let size = size.trim().trim_end_matches('K');
+    Some(size.parse::<Bytes>().ok()? * 1024)
+}
+
+/// Parses `MemTotal` out of `/proc/meminfo`, in bytes.
+fn meminfo_total() -> Option<Bytes> {
+    let contents = std::fs::read_to_string("/proc/meminfo").ok()?;
+
+    for line in contents.lines() {
+        if let Some(rest) = line.strip_prefix("MemTotal:") {
+            let kb = rest.trim().trim_end_matches(" kB").trim().parse::<Bytes>().ok()?;
+            return Some(kb * 1024);
+        }
+    }
+
+    None
+}
+
+/// Reads the active scaling governor for the first CPU core.
+fn cpu_governor() -> Option<String> {
+    std::fs::read_to_string("/sys/devices/system/cpu/cpu0/cpufreq/scaling_governor")
+        .ok()
+        .map(|v| v.trim().to_string())
+}
+
+/// Determines whether turbo/boost is enabled, checking both the generic `cpufreq/boost` knob
+/// and the Intel P-State driver's inverted `intel_pstate/no_turbo` knob.
+fn turbo_enabled() -> Option<bool> {
+    if let Ok(val) = std::fs::read_to_string("/sys/devices/system/cpu/cpufreq/boost") {
+        return Some(val.trim() == "1");
+    }
+
+    if let Ok(val) = std::fs::read_to_string("/sys/devices/system/cpu/intel_pstate/no_turbo") {
+        return Some(val.trim() == "0");
+    }
+
+    None
+}
+
+/// Returns a human-readable kernel/OS string, e.g. the contents of `/proc/version`.
+fn os_release() -> String {
+    std::fs::read_to_string("/proc/version")
+        .map(|v| v.trim().to_string())
+        .unwrap_or_else(|_| String::from("unknown"))
 }
\ No newline at end of file
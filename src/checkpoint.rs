@@ -0,0 +1,62 @@
+//! Persisted record of which (algorithm, benchmark, dataset) jobs a `bench` run has completed
+//! or given up on, so an interrupted sweep can be resumed without redoing already-finished work.
+
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::errors::POABenchError;
+use crate::jobs::{Algorithm, BenchmarkType};
+
+pub type JobKey = (Algorithm, BenchmarkType, String);
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Checkpoint {
+    completed: HashSet<JobKey>,
+    failed: HashSet<JobKey>,
+}
+
+impl Checkpoint {
+    fn path(output_dir: &Path) -> PathBuf {
+        output_dir.join("checkpoint.json")
+    }
+
+    /// Loads the checkpoint manifest from `output_dir`, or an empty one if it doesn't exist yet
+    /// (e.g. this is the first invocation of a sweep).
+    pub fn load(output_dir: &Path) -> Result<Self, POABenchError> {
+        let path = Self::path(output_dir);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let contents = std::fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&contents)?)
+    }
+
+    pub fn is_completed(&self, key: &JobKey) -> bool {
+        self.completed.contains(key)
+    }
+
+    /// Marks `key` as completed and persists the manifest, so a rerun of the same `bench`
+    /// invocation will skip it.
+    pub fn mark_completed(&mut self, key: JobKey, output_dir: &Path) -> Result<(), POABenchError> {
+        self.failed.remove(&key);
+        self.completed.insert(key);
+        self.save(output_dir)
+    }
+
+    /// Marks `key` as failed (after exhausting `--retries`) and persists the manifest. Failed
+    /// jobs are *not* skipped on a subsequent invocation, since the underlying issue may have
+    /// been fixed in the meantime.
+    pub fn mark_failed(&mut self, key: JobKey, output_dir: &Path) -> Result<(), POABenchError> {
+        self.failed.insert(key);
+        self.save(output_dir)
+    }
+
+    fn save(&self, output_dir: &Path) -> Result<(), POABenchError> {
+        let contents = serde_json::to_string_pretty(self)?;
+        std::fs::write(Self::path(output_dir), contents)?;
+        Ok(())
+    }
+}
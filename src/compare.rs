@@ -0,0 +1,379 @@
+//! Regression detection between two `bench` result TSV prefixes, e.g. a baseline run on master
+//! compared against a candidate run on a PR branch - the workflow CI uses to gate a PR on
+//! benchmark results.
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use clap::Args;
+use serde::Deserialize;
+
+use crate::errors::POABenchError;
+
+/// Column order `bench` writes to `<prefix>.single_seq.tsv`, kept here so the writer (in
+/// `main.rs`) and the reader (in this module) can't drift apart.
+pub const SINGLE_SEQ_HEADER: &[&str] = &[
+    "dataset", "algorithm", "graph_nodes", "graph_edges", "seq_name", "seq_length", "score",
+    "samples", "runtime_mean", "runtime_median", "runtime_stddev", "runtime_mad", "runtime_min", "runtime_max",
+    "num_visited_mean", "num_visited_median", "num_visited_stddev", "num_visited_mad", "num_visited_min", "num_visited_max",
+    "memory_mean", "memory_median", "memory_stddev", "memory_mad", "memory_min", "memory_max",
+];
+
+/// Column order `bench` writes to `<prefix>.full_msa.tsv`.
+pub const FULL_MSA_HEADER: &[&str] = &[
+    "dataset", "algorithm", "samples",
+    "runtime_mean", "runtime_median", "runtime_stddev", "runtime_mad", "runtime_min", "runtime_max",
+    "memory_mean", "memory_median", "memory_stddev", "memory_mad", "memory_min", "memory_max",
+];
+
+#[derive(Args, Debug, Clone)]
+pub struct CompareArgs {
+    /// Filename prefix of the baseline results, e.g. a run on master.
+    baseline: PathBuf,
+
+    /// Filename prefix of the candidate results, e.g. a run on a PR branch.
+    candidate: PathBuf,
+
+    /// Significance level (p-value cutoff) below which a runtime difference is flagged as a
+    /// regression, when both sides have at least 2 samples so Welch's t-test applies.
+    #[clap(long, default_value="0.05")]
+    alpha: f64,
+
+    /// Fraction by which runtime or memory is allowed to regress before being flagged. Used for
+    /// memory always, and for runtime as a fallback when either side has fewer than 2 samples
+    /// or both sides have zero variance (so Welch's t-test doesn't apply).
+    #[clap(long, default_value="0.05")]
+    threshold: f64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct SingleSeqRow {
+    dataset: String,
+    algorithm: String,
+    #[allow(dead_code)]
+    graph_nodes: usize,
+    #[allow(dead_code)]
+    graph_edges: usize,
+    seq_name: String,
+    #[allow(dead_code)]
+    seq_length: usize,
+    #[allow(dead_code)]
+    score: usize,
+    samples: usize,
+    runtime_mean: f64,
+    runtime_stddev: f64,
+    memory_mean: f64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct FullMSARow {
+    dataset: String,
+    algorithm: String,
+    samples: usize,
+    runtime_mean: f64,
+    runtime_stddev: f64,
+    memory_mean: f64,
+}
+
+/// Outcome of comparing one metric between a baseline and a candidate row.
+#[derive(Debug, Clone)]
+struct Delta {
+    dataset: String,
+    algorithm: String,
+    seq_name: Option<String>,
+    pct_change: f64,
+    method: &'static str,
+    is_regression: bool,
+}
+
+pub fn main(args: CompareArgs) -> Result<(), POABenchError> {
+    let single_seq_deltas = compare_single_seq(&args)?;
+    let full_msa_deltas = compare_full_msa(&args)?;
+
+    let mut writer = csv::WriterBuilder::new()
+        .delimiter(b'\t')
+        .from_writer(std::io::stdout());
+    writer.write_record(["dataset", "algorithm", "seq_name", "method", "pct_change", "verdict"])?;
+
+    let mut num_regressions = 0;
+    for delta in single_seq_deltas.iter().chain(full_msa_deltas.iter()) {
+        if delta.is_regression {
+            num_regressions += 1;
+        }
+
+        writer.write_record([
+            &delta.dataset,
+            &delta.algorithm,
+            delta.seq_name.as_deref().unwrap_or(""),
+            delta.method,
+            &format!("{:+.2}%", delta.pct_change * 100.0),
+            if delta.is_regression { "REGRESSION" } else { "ok" },
+        ])?;
+    }
+
+    writer.flush()?;
+
+    if num_regressions > 0 {
+        return Err(POABenchError::RegressionsDetected(num_regressions));
+    }
+
+    Ok(())
+}
+
+fn compare_single_seq(args: &CompareArgs) -> Result<Vec<Delta>, POABenchError> {
+    let baseline_fname = args.baseline.with_extension("single_seq.tsv");
+    let candidate_fname = args.candidate.with_extension("single_seq.tsv");
+
+    if !baseline_fname.exists() || !candidate_fname.exists() {
+        return Ok(Vec::new());
+    }
+
+    let baseline: HashMap<_, _> = read_rows::<SingleSeqRow>(&baseline_fname)?
+        .into_iter()
+        .map(|row| ((row.dataset.clone(), row.algorithm.clone(), row.seq_name.clone()), row))
+        .collect();
+
+    let mut deltas = Vec::new();
+    for candidate_row in read_rows::<SingleSeqRow>(&candidate_fname)? {
+        let key = (candidate_row.dataset.clone(), candidate_row.algorithm.clone(), candidate_row.seq_name.clone());
+        let Some(baseline_row) = baseline.get(&key) else {
+            continue;
+        };
+
+        deltas.push(runtime_delta(
+            &candidate_row.dataset, &candidate_row.algorithm, Some(candidate_row.seq_name.clone()),
+            baseline_row.runtime_mean, baseline_row.runtime_stddev, baseline_row.samples,
+            candidate_row.runtime_mean, candidate_row.runtime_stddev, candidate_row.samples,
+            args,
+        ));
+
+        deltas.push(memory_delta(
+            &candidate_row.dataset, &candidate_row.algorithm, Some(candidate_row.seq_name.clone()),
+            baseline_row.memory_mean, candidate_row.memory_mean, args,
+        ));
+    }
+
+    Ok(deltas)
+}
+
+fn compare_full_msa(args: &CompareArgs) -> Result<Vec<Delta>, POABenchError> {
+    let baseline_fname = args.baseline.with_extension("full_msa.tsv");
+    let candidate_fname = args.candidate.with_extension("full_msa.tsv");
+
+    if !baseline_fname.exists() || !candidate_fname.exists() {
+        return Ok(Vec::new());
+    }
+
+    let baseline: HashMap<_, _> = read_rows::<FullMSARow>(&baseline_fname)?
+        .into_iter()
+        .map(|row| ((row.dataset.clone(), row.algorithm.clone()), row))
+        .collect();
+
+    let mut deltas = Vec::new();
+    for candidate_row in read_rows::<FullMSARow>(&candidate_fname)? {
+        let key = (candidate_row.dataset.clone(), candidate_row.algorithm.clone());
+        let Some(baseline_row) = baseline.get(&key) else {
+            continue;
+        };
+
+        deltas.push(runtime_delta(
+            &candidate_row.dataset, &candidate_row.algorithm, None,
+            baseline_row.runtime_mean, baseline_row.runtime_stddev, baseline_row.samples,
+            candidate_row.runtime_mean, candidate_row.runtime_stddev, candidate_row.samples,
+            args,
+        ));
+
+        deltas.push(memory_delta(
+            &candidate_row.dataset, &candidate_row.algorithm, None,
+            baseline_row.memory_mean, candidate_row.memory_mean, args,
+        ));
+    }
+
+    Ok(deltas)
+}
+
+fn read_rows<T: serde::de::DeserializeOwned>(path: &std::path::Path) -> Result<Vec<T>, POABenchError> {
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(b'\t')
+        .from_path(path)?;
+
+    reader.deserialize().collect::<Result<Vec<T>, _>>().map_err(POABenchError::from)
+}
+
+/// Compares runtime between baseline and candidate, using Welch's t-test when both sides have
+/// at least 2 samples and not both a zero standard deviation, otherwise falling back to
+/// `args.threshold` on the percentage change.
+#[allow(clippy::too_many_arguments)]
+fn runtime_delta(
+    dataset: &str, algorithm: &str, seq_name: Option<String>,
+    baseline_mean: f64, baseline_stddev: f64, baseline_n: usize,
+    candidate_mean: f64, candidate_stddev: f64, candidate_n: usize,
+    args: &CompareArgs,
+) -> Delta {
+    let pct_change = (candidate_mean - baseline_mean) / baseline_mean;
+
+    match welch_t_test(baseline_mean, baseline_stddev, baseline_n, candidate_mean, candidate_stddev, candidate_n) {
+        Some(result) => Delta {
+            dataset: dataset.to_string(),
+            algorithm: algorithm.to_string(),
+            seq_name,
+            pct_change,
+            method: "welch_t_test",
+            is_regression: result.p_value < args.alpha && candidate_mean > baseline_mean,
+        },
+        None => Delta {
+            dataset: dataset.to_string(),
+            algorithm: algorithm.to_string(),
+            seq_name,
+            pct_change,
+            method: "pct_change",
+            is_regression: pct_change > args.threshold,
+        },
+    }
+}
+
+fn memory_delta(
+    dataset: &str, algorithm: &str, seq_name: Option<String>,
+    baseline_mean: f64, candidate_mean: f64, args: &CompareArgs,
+) -> Delta {
+    let pct_change = (candidate_mean - baseline_mean) / baseline_mean;
+
+    Delta {
+        dataset: dataset.to_string(),
+        algorithm: algorithm.to_string(),
+        seq_name,
+        pct_change,
+        method: "pct_change(memory)",
+        is_regression: pct_change > args.threshold,
+    }
+}
+
+struct WelchResult {
+    p_value: f64,
+}
+
+/// Welch's unequal-variance two-sample t-test, using the Welch-Satterthwaite approximation for
+/// degrees of freedom. Returns `None` when either side has fewer than 2 samples (so sample
+/// variance is undefined) or both sides have zero variance, whether or not the means differ.
+fn welch_t_test(
+    mean1: f64, stddev1: f64, n1: usize, mean2: f64, stddev2: f64, n2: usize,
+) -> Option<WelchResult> {
+    if n1 < 2 || n2 < 2 {
+        return None;
+    }
+
+    let n1 = n1 as f64;
+    let n2 = n2 as f64;
+    let var1_over_n1 = (stddev1 * stddev1) / n1;
+    let var2_over_n2 = (stddev2 * stddev2) / n2;
+
+    let standard_error = (var1_over_n1 + var2_over_n2).sqrt();
+    if standard_error == 0.0 {
+        return None;
+    }
+
+    let t = (mean2 - mean1) / standard_error;
+    let df = (var1_over_n1 + var2_over_n2).powi(2)
+        / (var1_over_n1.powi(2) / (n1 - 1.0) + var2_over_n2.powi(2) / (n2 - 1.0));
+
+    let x = df / (df + t * t);
+    let p_value = incomplete_beta(x, df / 2.0, 0.5);
+
+    Some(WelchResult { p_value })
+}
+
+/// Regularized incomplete beta function I_x(a, b). Used to turn a Welch t-statistic and its
+/// degrees of freedom into a two-tailed p-value, since `p = I_x(df/2, 1/2)` for
+/// `x = df / (df + t^2)`. Implementation follows the continued-fraction method from Numerical
+/// Recipes (`betai`/`betacf`).
+fn incomplete_beta(x: f64, a: f64, b: f64) -> f64 {
+    if x <= 0.0 {
+        return 0.0;
+    }
+    if x >= 1.0 {
+        return 1.0;
+    }
+
+    let ln_beta = ln_gamma(a + b) - ln_gamma(a) - ln_gamma(b);
+    let front = (ln_beta + a * x.ln() + b * (1.0 - x).ln()).exp();
+
+    if x < (a + 1.0) / (a + b + 2.0) {
+        front * beta_continued_fraction(x, a, b) / a
+    } else {
+        1.0 - front * beta_continued_fraction(1.0 - x, b, a) / b
+    }
+}
+
+fn beta_continued_fraction(x: f64, a: f64, b: f64) -> f64 {
+    const MAX_ITER: usize = 200;
+    const EPS: f64 = 1e-10;
+    const TINY: f64 = 1e-30;
+
+    let qab = a + b;
+    let qap = a + 1.0;
+    let qam = a - 1.0;
+
+    let mut c = 1.0_f64;
+    let mut d = 1.0 - qab * x / qap;
+    if d.abs() < TINY {
+        d = TINY;
+    }
+    d = 1.0 / d;
+    let mut h = d;
+
+    for m in 1..=MAX_ITER {
+        let m_f = m as f64;
+        let m2 = 2.0 * m_f;
+
+        let aa = m_f * (b - m_f) * x / ((qam + m2) * (a + m2));
+        d = 1.0 + aa * d;
+        if d.abs() < TINY {
+            d = TINY;
+        }
+        c = 1.0 + aa / c;
+        if c.abs() < TINY {
+            c = TINY;
+        }
+        d = 1.0 / d;
+        h *= d * c;
+
+        let aa = -(a + m_f) * (qab + m_f) * x / ((a + m2) * (qap + m2));
+        d = 1.0 + aa * d;
+        if d.abs() < TINY {
+            d = TINY;
+        }
+        c = 1.0 + aa / c;
+        if c.abs() < TINY {
+            c = TINY;
+        }
+        d = 1.0 / d;
+        let delta = d * c;
+        h *= delta;
+
+        if (delta - 1.0).abs() < EPS {
+            break;
+        }
+    }
+
+    h
+}
+
+/// Lanczos approximation of ln(Gamma(x)), accurate to ~1e-10 for x > 0.
+fn ln_gamma(xx: f64) -> f64 {
+    const COEFFS: [f64; 6] = [
+        76.18009172947146, -86.50532032941677, 24.01409824083091,
+        -1.231739572450155, 0.1208650973866179e-2, -0.5395239384953e-5,
+    ];
+
+    let mut y = xx;
+    let mut tmp = xx + 5.5;
+    tmp -= (xx + 0.5) * tmp.ln();
+
+    let mut ser = 1.000000000190015_f64;
+    for c in COEFFS {
+        y += 1.0;
+        ser += c / y;
+    }
+
+    -tmp + (2.5066282746310005 * ser / xx).ln()
+}
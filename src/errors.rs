@@ -16,9 +16,10 @@ pub enum POABenchError {
     PoastaError(PoastaError),
     JSONError(serde_json::Error),
     ParseConfigError(toml::de::Error),
-    WorkerError,
     MemoryResetError,
     TSVError(csv::Error),
+    VerificationError(String),
+    RegressionsDetected(usize),
 }
 
 impl Error for POABenchError {
@@ -32,9 +33,10 @@ impl Error for POABenchError {
             Self::PoastaError(source) => Some(source),
             Self::JSONError(source) => Some(source),
             Self::ParseConfigError(source) => Some(source),
-            Self::WorkerError => None,
             Self::MemoryResetError => None,
             Self::TSVError(source) => Some(source),
+            Self::VerificationError(_) => None,
+            Self::RegressionsDetected(_) => None,
         }
     }
 }
@@ -62,12 +64,15 @@ impl Display for POABenchError {
                 write!(f, "Could not parse dataset config: ")?;
                 fmt::Display::fmt(source, f)
             },
-            Self::WorkerError => write!(f, "A worker process did not exit properly!"),
             Self::MemoryResetError => write!(f, "Platform does not support resetting max_rss, memory usage measurements will be incorrect!"),
             Self::TSVError(source) => {
                 write!(f, "Could not write results to TSV! ")?;
                 fmt::Display::fmt(source, f)
             }
+            Self::VerificationError(msg) =>
+                write!(f, "Verification failed: {}", msg),
+            Self::RegressionsDetected(count) =>
+                write!(f, "Found {} regression(s) in the candidate results!", count),
         }
     }
 }
@@ -0,0 +1,70 @@
+//! Length-prefixed framing for worker -> orchestrator `JobResult` IPC.
+//!
+//! Each message is a 4-byte little-endian length prefix followed by the JSON-serialized
+//! `JobResult`, written to a dedicated pipe rather than stdout. This keeps stdout free for
+//! human-readable logging and avoids ambiguity from embedded newlines (or a stray debug print)
+//! corrupting the stream.
+
+use std::fs::File;
+use std::io::{self, BufWriter, Read, Write};
+use std::os::fd::FromRawFd;
+
+use crate::errors::POABenchError;
+use crate::jobs::JobResult;
+
+/// File descriptor the worker writes its framed `JobResult`s to, and the orchestrator reads
+/// them from. Fixed so both sides agree on it without needing to renegotiate.
+pub const RESULT_FD: i32 = 3;
+
+/// Opens the writer job results are sent to: the framed pipe at `result_fd` when the
+/// orchestrator passed one, or stdout as JSON lines otherwise (e.g. when running the worker by
+/// hand for debugging).
+pub fn result_writer(result_fd: Option<i32>) -> Box<dyn Write> {
+    match result_fd {
+        Some(fd) => Box::new(BufWriter::new(unsafe { File::from_raw_fd(fd) })),
+        None => Box::new(std::io::stdout()),
+    }
+}
+
+/// Sends a single `JobResult` to `writer`, framed when `result_fd` is set and as a JSON line
+/// otherwise.
+pub fn send_result(writer: &mut dyn Write, result_fd: Option<i32>, result: &JobResult) -> Result<(), POABenchError> {
+    if result_fd.is_some() {
+        write_result(writer, result)
+    } else {
+        writeln!(writer, "{}", serde_json::to_string(result)?)?;
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+/// Writes a single framed `JobResult` to `writer` and flushes, so results are visible to the
+/// orchestrator as soon as each job finishes rather than sitting in a buffer.
+pub fn write_result<W: Write>(writer: &mut W, result: &JobResult) -> Result<(), POABenchError> {
+    let payload = serde_json::to_vec(result)?;
+    let len = payload.len() as u32;
+
+    writer.write_all(&len.to_le_bytes())?;
+    writer.write_all(&payload)?;
+    writer.flush()?;
+
+    Ok(())
+}
+
+/// Reads a single framed `JobResult` from `reader`, or `None` on a clean EOF between frames.
+pub fn read_result<R: Read>(reader: &mut R) -> Result<Option<JobResult>, POABenchError> {
+    let mut len_buf = [0u8; 4];
+    if let Err(e) = reader.read_exact(&mut len_buf) {
+        return if e.kind() == io::ErrorKind::UnexpectedEof {
+            Ok(None)
+        } else {
+            Err(e.into())
+        };
+    }
+
+    let len = u32::from_le_bytes(len_buf) as usize;
+    let mut payload = vec![0u8; len];
+    reader.read_exact(&mut payload)?;
+
+    Ok(Some(serde_json::from_slice(&payload)?))
+}
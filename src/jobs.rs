@@ -1,26 +1,30 @@
 use clap::ValueEnum;
 use serde::{Deserialize, Serialize};
-use crate::bench::Measured;
+use crate::bench::{Measured, SystemInfo};
 
-#[derive(Copy, Clone, Debug, ValueEnum, Serialize, Deserialize)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, ValueEnum, Serialize, Deserialize)]
 pub enum Algorithm {
     POASTA,
-    SPOA
+    SPOA,
+    #[allow(non_camel_case_types)]
+    #[value(name = "abpoa")]
+    abPOA
 }
 
 impl Algorithm {
     pub fn to_str(&self) -> &str {
         match self {
             Self::POASTA => "poasta",
-            Self::SPOA => "spoa"
+            Self::SPOA => "spoa",
+            Self::abPOA => "abpoa"
         }
     }
 }
 
-pub static ALL_ALGORITHMS: &[Algorithm] = &[Algorithm::POASTA, Algorithm::SPOA];
+pub static ALL_ALGORITHMS: &[Algorithm] = &[Algorithm::POASTA, Algorithm::SPOA, Algorithm::abPOA];
 
 
-#[derive(Copy, Clone, Debug, PartialEq, Eq, ValueEnum, Serialize, Deserialize)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, ValueEnum, Serialize, Deserialize)]
 pub enum BenchmarkType {
     SingleSequence,
     FullMSA,
@@ -51,16 +55,26 @@ pub struct Job {
 /// Used for worker-orchestrator IPC
 #[derive(Debug, Serialize, Deserialize)]
 pub enum JobResult {
-    /// Variant to indicate new measurement results from single sequence alignment
-    SingleSeqMeasurement(Algorithm, String, usize, usize, usize, String, usize, usize, Measured),
+    /// Variant to indicate the system/hardware profile of the machine running the worker,
+    /// emitted once at worker startup so results can be compared across machines
+    SystemProfile(SystemInfo),
 
-    /// New measurement from the full MSA benchmark
-    FullMSAMeasurement(Algorithm, String, Measured),
+    /// Variant to indicate new measurement results from single sequence alignment. The last
+    /// `usize` is the 0-based replicate index, for when `--samples` requests more than one
+    /// measurement per sequence; the orchestrator aggregates across replicates before writing
+    /// the TSV row.
+    SingleSeqMeasurement(Algorithm, String, usize, usize, usize, String, usize, usize, Measured, usize),
+
+    /// New measurement from the full MSA benchmark. The last `usize` is the 0-based replicate
+    /// index, see `SingleSeqMeasurement`.
+    FullMSAMeasurement(Algorithm, String, Measured, usize),
 
     /// Variant to indicate the whole dataset has been processed and optionally indicates which
-    /// processor core is now free
-    Finished(Option<usize>),
+    /// processor core is now free. Tagged with the job's identity so the orchestrator can record
+    /// it as completed in the checkpoint manifest.
+    Finished(Option<usize>, Algorithm, BenchmarkType, String),
 
-    /// Variant to indicate that a worker did not properly exit correctly
-    Error,
+    /// Variant to indicate that a worker did not properly exit correctly. Tagged with the job's
+    /// identity (for checkpointing/retries) and which core is now free.
+    Error(Option<usize>, Algorithm, BenchmarkType, String),
 }
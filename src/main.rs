@@ -2,7 +2,9 @@ use std::{fs, process, thread};
 use std::fmt::Debug;
 use std::error::Error;
 use std::fs::File;
-use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::io::{BufReader, BufWriter, Write};
+use std::os::fd::FromRawFd;
+use std::os::unix::process::CommandExt;
 use std::path::{Path, PathBuf};
 use std::sync::mpsc;
 
@@ -13,13 +15,22 @@ use core_affinity::CoreId;
 
 use errors::POABenchError;
 use crate::dataset::{Dataset, find_datasets};
+use crate::ipc;
 use crate::jobs::{Algorithm, BenchmarkType, JobResult};
+use crate::profiler::Profiler;
 
 mod errors;
 mod worker;
 mod dataset;
 mod jobs;
 mod bench;
+mod ipc;
+mod aligner;
+mod verify;
+mod profiler;
+mod compare;
+mod checkpoint;
+mod manifest;
 
 #[derive(Parser, Debug, Clone)]
 struct CliArgs {
@@ -31,6 +42,7 @@ struct CliArgs {
 enum Command {
     Bench(BenchArgs),
     Worker(worker::WorkerArgs),
+    Compare(compare::CompareArgs),
 }
 
 
@@ -65,9 +77,61 @@ struct BenchArgs {
     /// The filename prefix should *not* contain any directories, use the `output_dir` setting for that.
     #[clap(short='f', long, default_value="results")]
     results_prefix: PathBuf,
+
+    /// Verify correctness alongside the performance numbers: each worker double-checks its own
+    /// reported scores (currently only supported for POASTA), and scores for the same sequence
+    /// are compared across algorithms, aborting the run if any disagree beyond `score_tolerance`.
+    /// Only POASTA and SPOA scores take part in that comparison; abPOA's are not compared (see
+    /// `verify::check_score_agreement`).
+    #[clap(long)]
+    verify: bool,
+
+    /// Fraction by which per-sequence scores from different algorithms are allowed to differ
+    /// before `--verify` flags them as a regression. Only used when `--verify` is set.
+    #[clap(long, default_value="0.05")]
+    score_tolerance: f64,
+
+    /// Number of timed replicate measurements to take per sequence (or per full-MSA build). Each
+    /// worker reports replicates individually, tagged with a 0-based index; the orchestrator
+    /// buffers them per job and writes a single TSV row with the mean, median, sample standard
+    /// deviation, min and max across replicates.
+    #[clap(long, default_value="1")]
+    samples: usize,
+
+    /// Number of untimed warmup replicates the worker runs per sequence (or per full-MSA build)
+    /// before the timed ones, to let caches/allocators settle.
+    #[clap(long, default_value="0")]
+    warmup: usize,
+
+    /// Poll and record memory/CPU usage at this interval (in milliseconds) while each alignment
+    /// runs. Disabled by default since the sampling thread adds a small but nonzero overhead.
+    /// When set, the time series for each replicate is written to
+    /// `output_dir/<dataset>/<algorithm>[.<seq_name>].<replicate>.samples.json`.
+    #[clap(long)]
+    sample_interval_ms: Option<u64>,
+
+    /// Attach one or more profilers/resource monitors to each worker process. Artifacts are
+    /// written to `output_dir/<dataset>/<algorithm>.<profiler>.{txt,json}`: `perf` writes its
+    /// `perf script` stack samples as text (no SVG flamegraph is rendered), `samply` and
+    /// `sys-monitor` write JSON. To specify multiple, separate them by spaces.
+    #[clap(value_enum, long, num_args=0..)]
+    profilers: Vec<Profiler>,
+
+    /// Number of times to re-enqueue a job whose worker process exited with an error before
+    /// giving up on it and recording it as failed in the checkpoint manifest. A failing job no
+    /// longer aborts the whole run; see `checkpoint` module.
+    #[clap(long, default_value="0")]
+    retries: usize,
 }
 
 
+/// Exact SPOA argument vector used to build every dataset's graph. Pulled out into a constant
+/// (rather than inlined at the one call site) so `manifest` can record it verbatim in the run
+/// provenance manifest without risking it drifting out of sync.
+const SPOA_BUILD_GRAPH_ARGS: &[&str] = &[
+    "-l", "1", "-m", "0", "-n", "-4", "-g", "-8", "-e", "-2", "-q", "0", "-c", "0", "-r", "1"
+];
+
 fn build_graph_with_spoa(output_dir: &Path, dataset: &Dataset) -> Result<()> {
     let Some(seq_fname) = dataset.graph_sequences_fname() else {
         eprintln!("Dataset {} has no graph seqeuence set, skipping.", dataset.name());
@@ -90,7 +154,7 @@ fn build_graph_with_spoa(output_dir: &Path, dataset: &Dataset) -> Result<()> {
     fs::create_dir_all(dataset.output_dir(output_dir))?;
 
     let process = process::Command::new("spoa")
-        .args(["-l", "1", "-m", "0", "-n", "-4", "-g", "-8", "-e", "-2", "-q", "0", "-c", "0", "-r", "1"])
+        .args(SPOA_BUILD_GRAPH_ARGS)
         .arg(&seq_fname)
         .output()
         .context("Could not run SPOA, is it installed and available in $PATH?")?;
@@ -122,6 +186,58 @@ fn build_graphs(output_dir: &Path, datasets: &[Dataset]) -> Result<()> {
     Ok(())
 }
 
+/// Opens `path` for appending, writing `header` first only if the file doesn't already exist --
+/// so a resumed `bench` invocation keeps the rows an earlier invocation already wrote instead of
+/// truncating them.
+fn open_results_tsv(path: &Path, header: &[&str]) -> Result<csv::Writer<File>> {
+    let needs_header = !path.exists();
+
+    let file = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?;
+
+    let mut writer = csv::WriterBuilder::new()
+        .delimiter(b'\t')
+        .from_writer(file);
+
+    if needs_header {
+        writer.write_record(header)?;
+    }
+
+    Ok(writer)
+}
+
+/// Writes `samples` (the resource usage time series `bench::measure` recorded for one replicate)
+/// to `output_dir/<dataset>/<algorithm>[.<seq_name>].<replicate>.samples.json`, following the
+/// `<dataset>/<algorithm>.<artifact>` layout `profiler::attach` uses for its own artifacts. A
+/// no-op when `samples` is empty, which is the normal case when `--sample-interval-ms` wasn't set.
+fn write_resource_samples(
+    output_dir: &Path,
+    dataset: &str,
+    algorithm: Algorithm,
+    seq_name: Option<&str>,
+    replicate: usize,
+    samples: &[bench::ResourceSample],
+) -> Result<()> {
+    if samples.is_empty() {
+        return Ok(());
+    }
+
+    let dataset_dir = output_dir.join(dataset);
+    fs::create_dir_all(&dataset_dir)?;
+
+    let fname = match seq_name {
+        Some(seq_name) => format!("{}.{seq_name}.{replicate}.samples.json", algorithm.to_str()),
+        None => format!("{}.{replicate}.samples.json", algorithm.to_str()),
+    };
+
+    let file = File::create(dataset_dir.join(fname))?;
+    serde_json::to_writer(file, samples)?;
+
+    Ok(())
+}
+
 
 fn sort_sequences_by_genetic_distance(output_dir: &Path, dataset: &Dataset) -> Result<()> {
     let graph_seq_fname = dataset.graph_sequences_fname();
@@ -239,6 +355,11 @@ fn run_job<'scope, 'env>(
     benchmark_type: BenchmarkType,
     dataset: &'scope Dataset,
     core: Option<CoreId>,
+    verify: bool,
+    samples: usize,
+    warmup: usize,
+    sample_interval_ms: Option<u64>,
+    profilers: &'scope [Profiler],
     tx: mpsc::Sender<JobResult>
 )
 where
@@ -247,45 +368,142 @@ where
     eprintln!("STARTING JOB algorithm: {:?}, dataset: {:?}", algorithm, dataset.name());
 
     scope.spawn(move || {
-        let mut command = process::Command::new(proc_exe);
+        // `run_worker_job` reports the job's own outcome (a bad exit status becomes an `Error`
+        // frame) as part of its normal, successful return. An `Err` here means something went
+        // wrong *before* that could happen -- e.g. the worker was killed mid-frame and
+        // `ipc::read_result` saw a truncated payload instead of a clean disconnect -- so no
+        // `Finished`/`Error` has been sent yet. Without a fallback here, `in_flight` in `bench`'s
+        // receive loop would never be decremented for this job and the whole run would hang.
+        if let Err(err) = run_worker_job(
+            proc_exe, datasets_dir, output_dir, algorithm, benchmark_type, dataset,
+            core, verify, samples, warmup, sample_interval_ms, profilers, &tx,
+        ) {
+            eprintln!(
+                "Job thread failed before it could report a result (algorithm={:?} benchmark={:?} dataset={}): {}",
+                algorithm, benchmark_type, dataset.name(), err
+            );
+            let _ = tx.send(JobResult::Error(
+                core.map(|v| v.id), algorithm, benchmark_type, dataset.name().to_string()
+            ));
+        }
+    });
+}
 
-        command
-            .arg("worker")
-            .arg("-d")
-            .arg(datasets_dir)
-            .arg("-o")
-            .arg(output_dir);
+fn run_worker_job(
+    proc_exe: &Path,
+    datasets_dir: &Path,
+    output_dir: &Path,
+    algorithm: Algorithm,
+    benchmark_type: BenchmarkType,
+    dataset: &Dataset,
+    core: Option<CoreId>,
+    verify: bool,
+    samples: usize,
+    warmup: usize,
+    sample_interval_ms: Option<u64>,
+    profilers: &[Profiler],
+    tx: &mpsc::Sender<JobResult>,
+) -> Result<()> {
+    let mut command = process::Command::new(proc_exe);
+
+    command
+        .arg("worker")
+        .arg("-d")
+        .arg(datasets_dir)
+        .arg("-o")
+        .arg(output_dir)
+        .arg("-r")
+        .arg(ipc::RESULT_FD.to_string());
+
+    if let Some(core_id) = core {
+        command.arg("-c")
+            .arg(format!("{}", core_id.id));
+    }
 
-        if let Some(core_id) = core {
-            command.arg("-c")
-                .arg(format!("{}", core_id.id));
-        }
+    if verify {
+        command.arg("--verify");
+    }
 
-        command
-            .arg(dataset.name())
-            .arg(algorithm.to_str())
-            .arg(benchmark_type.to_str())
-            .stdout(process::Stdio::piped());
+    command
+        .arg("--repeat")
+        .arg(samples.to_string())
+        .arg("--warmup")
+        .arg(warmup.to_string());
 
-        eprintln!("Running command: {:?}", command);
-        let mut child = command.spawn()?;
+    if let Some(interval_ms) = sample_interval_ms {
+        command.arg("--sample-interval-ms").arg(interval_ms.to_string());
+    }
 
-        let reader = BufReader::new(child.stdout.as_mut().unwrap());
+    command
+        .arg(dataset.name())
+        .arg(algorithm.to_str())
+        .arg(benchmark_type.to_str());
+
+    // Open a dedicated pipe for framed job results, leaving stdout free for the worker's
+    // own human-readable logging. The write end is handed to the child as fd `RESULT_FD`.
+    //
+    // Opened with O_CLOEXEC so a sibling job spawned concurrently from another `scope.spawn`
+    // thread (with `--parallel > 1`) can never inherit this pipe's write end across its own
+    // `exec` -- the `pre_exec` below only closes *this* job's fds, so without O_CLOEXEC a
+    // sibling's worker process would hold this write end open for as long as it runs, and this
+    // job's `read_result` loop wouldn't see EOF until that unrelated worker also exited. `dup2`
+    // always clears FD_CLOEXEC on its destination, so the dup'd `RESULT_FD` the child actually
+    // uses is unaffected and survives its own `exec`.
+    let mut fds: [libc::c_int; 2] = [0; 2];
+    if unsafe { libc::pipe2(fds.as_mut_ptr(), libc::O_CLOEXEC) } != 0 {
+        return Err(POABenchError::IOError(std::io::Error::last_os_error()).into());
+    }
+    let (read_fd, write_fd) = (fds[0], fds[1]);
 
-        for line in reader.lines().map_while(Result::ok) {
-            let job_result: serde_json::Result<JobResult> = serde_json::from_str(&line);
-            match job_result {
-                Ok(result) => tx.send(result)?,
-                Err(e) => eprintln!("ERROR Could not parse job result from line: {}\nERROR {}", line, e)
+    unsafe {
+        command.pre_exec(move || {
+            if libc::dup2(write_fd, ipc::RESULT_FD) < 0 {
+                return Err(std::io::Error::last_os_error());
             }
-        }
 
-        if !child.wait().expect("Could not launch worker!").success() {
-            tx.send(JobResult::Error)?
+            // Close the original fds now that `RESULT_FD` holds the write end -- unless one
+            // of them *was* `RESULT_FD`, in which case closing it here would close the dup'd
+            // copy out from under the child instead of the original.
+            if write_fd != ipc::RESULT_FD {
+                libc::close(write_fd);
+            }
+            if read_fd != ipc::RESULT_FD {
+                libc::close(read_fd);
+            }
+
+            Ok(())
+        });
+    }
+
+    eprintln!("Running command: {:?}", command);
+    let mut child = command.spawn()?;
+    unsafe { libc::close(write_fd) };
+
+    let profiler_handles: Vec<_> = profilers.iter()
+        .map(|p| profiler::attach(*p, child.id(), output_dir, dataset.name(), algorithm.to_str()))
+        .collect::<Result<_, _>>()?;
+
+    let mut result_pipe = BufReader::new(unsafe { File::from_raw_fd(read_fd) });
+
+    while let Some(result) = ipc::read_result(&mut result_pipe)? {
+        tx.send(result)?
+    }
+
+    let status = child.wait().expect("Could not launch worker!");
+
+    // The worker's `Finished` has already been forwarded by now, so a profiler failure is only
+    // logged: returning it would make `run_job` send a second, spurious `Error` for this job.
+    for handle in profiler_handles {
+        if let Err(err) = profiler::finish(handle) {
+            eprintln!("Could not write profiler artifact for {}/{}: {}", dataset.name(), algorithm.to_str(), err);
         }
+    }
 
-        Ok::<(), POABenchError>(())
-    });
+    if !status.success() {
+        tx.send(JobResult::Error(core.map(|v| v.id), algorithm, benchmark_type, dataset.name().to_string()))?
+    }
+
+    Ok(())
 }
 
 
@@ -299,6 +517,9 @@ fn bench(bench_args: BenchArgs) -> Result<()> {
     eprintln!("Creating sorted FASTAs...");
     create_sorted_fastas(&bench_args.output_dir, &datasets)?;
 
+    eprintln!("Loading checkpoint manifest...");
+    let mut checkpoint = checkpoint::Checkpoint::load(&bench_args.output_dir)?;
+
     eprintln!("Building job list...");
     let algorithms: &[Algorithm] = if !bench_args.algorithms.is_empty() {
         &bench_args.algorithms
@@ -312,6 +533,10 @@ fn bench(bench_args: BenchArgs) -> Result<()> {
         jobs::ALL_BENCHMARK_TYPES
     };
 
+    let dataset_lookup: std::collections::HashMap<&str, &Dataset> = datasets.iter()
+        .map(|d| (d.name(), d))
+        .collect();
+
     let mut jobs = Vec::new();
     for algorithm in algorithms {
         for benchmark in benchmarks {
@@ -324,6 +549,11 @@ fn bench(bench_args: BenchArgs) -> Result<()> {
                     continue;
                 }
 
+                if checkpoint.is_completed(&(*algorithm, *benchmark, dataset.name().to_string())) {
+                    eprintln!("Skipping already-completed job: {:?}/{:?}/{}", algorithm, benchmark, dataset.name());
+                    continue;
+                }
+
                 jobs.push((algorithm, benchmark, dataset))
             }
         }
@@ -342,48 +572,78 @@ fn bench(bench_args: BenchArgs) -> Result<()> {
 
     let worker_cores: Vec<_> = cores.collect();
     let (tx, rx) = mpsc::channel();
-    let mut job_txs = Vec::with_capacity(jobs.len());
-    job_txs.push(tx);
-
-    if jobs.len() > 1 {
-        for _ in &jobs[1..] {
-            job_txs.push(job_txs[0].clone());
-        }
-    }
-
-    let results_single_fname = bench_args.results_prefix
-        .with_extension("single_seq.tsv");
-    let results_full_msa_fname = bench_args.results_prefix
-        .with_extension("full_msa.tsv");
 
-    let mut tsv_writer_single = csv::WriterBuilder::new()
-        .delimiter(b'\t')
-        .from_path(bench_args.output_dir.join(results_single_fname))?;
-
-    let mut tsv_writer_msa = csv::WriterBuilder::new()
-        .delimiter(b'\t')
-        .from_path(bench_args.output_dir.join(results_full_msa_fname))?;
+    let results_single_path = bench_args.output_dir.join(
+        bench_args.results_prefix.with_extension("single_seq.tsv")
+    );
+    let results_full_msa_path = bench_args.output_dir.join(
+        bench_args.results_prefix.with_extension("full_msa.tsv")
+    );
+
+    // Appended to (rather than truncated) so a resumed sweep -- which skips jobs the checkpoint
+    // manifest already marked completed -- doesn't lose the rows those earlier jobs wrote. The
+    // header is only written once, when the file doesn't exist yet.
+    let mut tsv_writer_single = open_results_tsv(&results_single_path, compare::SINGLE_SEQ_HEADER)?;
+    let mut tsv_writer_msa = open_results_tsv(&results_full_msa_path, compare::FULL_MSA_HEADER)?;
+
+    eprintln!("Writing run provenance manifest...");
+    let mut run_manifest = manifest::RunManifest::capture(
+        &bench_args.datasets_dir, &datasets, orchestrator_core.id,
+        &worker_cores.iter().map(|c| c.id).collect::<Vec<_>>(), bench_args.parallel
+    );
+    run_manifest.write(&bench_args.output_dir)?;
 
     thread::scope(|scope| -> Result<()> {
-        let mut job_iter = jobs.into_iter().zip(job_txs.into_iter());
+        let mut job_iter = jobs.into_iter();
+
+        // Number of jobs dispatched but not yet resolved (by a `Finished` or a terminal
+        // `Error`). The results channel is never allowed to disconnect -- every dispatch holds
+        // its own `tx.clone()` -- so the loop below exits on this counter reaching zero rather
+        // than on the channel disconnecting.
+        let mut in_flight: usize = 0;
 
         // Start initial jobs, limited to number of worker cores
         for core in &worker_cores {
-            if let Some(((algorithm, benchmark, dataset), job_tx)) = job_iter.next() {
+            if let Some((algorithm, benchmark, dataset)) = job_iter.next() {
                 run_job(
                     scope, &proc_exe, &bench_args.datasets_dir, &bench_args.output_dir,
-                    *algorithm, *benchmark, dataset, Some(*core), job_tx
+                    *algorithm, *benchmark, dataset, Some(*core), bench_args.verify,
+                    bench_args.samples, bench_args.warmup, bench_args.sample_interval_ms,
+                    &bench_args.profilers, tx.clone()
                 );
+                in_flight += 1;
             } else {
                 break;
             }
         }
 
-        // Receive results, and start new jobs when another finishes
-        for result in rx {
+        // Scores for the same (dataset, seq_name) pair collected across algorithms, so they can
+        // be cross-checked once all algorithms have reported a result for that sequence. Only
+        // populated when `--verify` is set.
+        let mut score_comparisons: std::collections::HashMap<(String, String), verify::ScoreComparison> = std::collections::HashMap::new();
+
+        // Replicates received so far for a single-sequence job, keyed by (dataset, algorithm,
+        // seq_name), buffered until all of `bench_args.samples` have arrived so they can be
+        // aggregated into one TSV row.
+        let mut single_seq_buffer: std::collections::HashMap<(String, Algorithm, String), Vec<(usize, usize, usize, usize, usize, bench::Measured)>> = std::collections::HashMap::new();
+
+        // Same as `single_seq_buffer`, but for full-MSA jobs, keyed by (dataset, algorithm).
+        let mut full_msa_buffer: std::collections::HashMap<(String, Algorithm), Vec<bench::Measured>> = std::collections::HashMap::new();
+
+        // Number of times each job has already been retried after an `Error`, so `--retries`
+        // can be enforced. Not persisted: a rerun of an interrupted sweep gets a fresh budget.
+        let mut attempts: std::collections::HashMap<checkpoint::JobKey, usize> = std::collections::HashMap::new();
+
+        // Receive results, and start new jobs when another finishes, until the job queue is
+        // drained and every dispatched job has resolved.
+        while in_flight > 0 {
+            let result = rx.recv().expect("a sender was dropped while its job was still in flight");
             eprintln!("Got result: {:?}", result);
 
             match result {
+                JobResult::SystemProfile(system_info) => {
+                    eprintln!("Worker system profile: {:?}", system_info);
+                },
                 JobResult::SingleSeqMeasurement(
                     algo,
                     dataset,
@@ -393,8 +653,36 @@ fn bench(bench_args: BenchArgs) -> Result<()> {
                     seq_name,
                     seq_length,
                     num_visited,
-                    measured
+                    measured,
+                    replicate
                 ) => {
+                    write_resource_samples(
+                        &bench_args.output_dir, &dataset, algo, Some(&seq_name), replicate, &measured.samples
+                    )?;
+
+                    let key = (dataset.clone(), algo, seq_name.clone());
+                    let replicates = single_seq_buffer.entry(key.clone()).or_default();
+                    replicates.push((score, graph_nodes, graph_edges, seq_length, num_visited, measured));
+
+                    if replicates.len() < bench_args.samples.max(1) {
+                        continue;
+                    }
+
+                    let replicates = single_seq_buffer.remove(&key).unwrap();
+                    let (dataset, algo, seq_name) = key;
+
+                    let runtime_stats = bench::compute_stats(
+                        &replicates.iter().map(|(_, _, _, _, _, m)| m.runtime as f64).collect::<Vec<_>>()
+                    ).expect("at least one replicate");
+                    let num_visited_stats = bench::compute_stats(
+                        &replicates.iter().map(|(_, _, _, _, n, _)| *n as f64).collect::<Vec<_>>()
+                    ).expect("at least one replicate");
+                    let memory_stats = bench::compute_stats(
+                        &replicates.iter().map(|(_, _, _, _, _, m)| m.memory as f64).collect::<Vec<_>>()
+                    ).expect("at least one replicate");
+
+                    let (score, graph_nodes, graph_edges, seq_length, _, _) = replicates[0];
+
                     tsv_writer_single.write_record([
                         &dataset,
                         algo.to_str(),
@@ -403,41 +691,134 @@ fn bench(bench_args: BenchArgs) -> Result<()> {
                         &seq_name,
                         &seq_length.to_string(),
                         &score.to_string(),
-                        &num_visited.to_string(),
-                        &measured.runtime.to_string(),
-                        &measured.memory_initial.map_or(String::default(), |v| v.to_string()),
-                        &measured.memory_total.map_or(String::default(), |v| v.to_string()),
-                        &measured.memory.to_string(),
-                        &measured.time_start.to_string(),
-                        &measured.time_end.to_string()
+                        &replicates.len().to_string(),
+                        &runtime_stats.mean.to_string(),
+                        &runtime_stats.median.to_string(),
+                        &runtime_stats.stddev.to_string(),
+                        &runtime_stats.mad.to_string(),
+                        &runtime_stats.min.to_string(),
+                        &runtime_stats.max.to_string(),
+                        &num_visited_stats.mean.to_string(),
+                        &num_visited_stats.median.to_string(),
+                        &num_visited_stats.stddev.to_string(),
+                        &num_visited_stats.mad.to_string(),
+                        &num_visited_stats.min.to_string(),
+                        &num_visited_stats.max.to_string(),
+                        &memory_stats.mean.to_string(),
+                        &memory_stats.median.to_string(),
+                        &memory_stats.stddev.to_string(),
+                        &memory_stats.mad.to_string(),
+                        &memory_stats.min.to_string(),
+                        &memory_stats.max.to_string(),
                     ])?;
+
+                    if bench_args.verify {
+                        let key = (dataset.clone(), seq_name.clone());
+                        let scores = score_comparisons.entry(key).or_default();
+                        scores.push((algo, score));
+
+                        if scores.len() == algorithms.len() {
+                            if let Some(msg) = verify::check_score_agreement(&dataset, &seq_name, scores, bench_args.score_tolerance) {
+                                return Err(POABenchError::VerificationError(msg).into());
+                            }
+                        }
+                    }
                 },
-                JobResult::FullMSAMeasurement(algo, dataset, measured) => {
+                JobResult::FullMSAMeasurement(algo, dataset, measured, replicate) => {
+                    write_resource_samples(
+                        &bench_args.output_dir, &dataset, algo, None, replicate, &measured.samples
+                    )?;
+
+                    let key = (dataset.clone(), algo);
+                    let replicates = full_msa_buffer.entry(key.clone()).or_default();
+                    replicates.push(measured);
+
+                    if replicates.len() < bench_args.samples.max(1) {
+                        continue;
+                    }
+
+                    let replicates = full_msa_buffer.remove(&key).unwrap();
+                    let (dataset, algo) = key;
+
+                    let runtime_stats = bench::compute_stats(
+                        &replicates.iter().map(|m| m.runtime as f64).collect::<Vec<_>>()
+                    ).expect("at least one replicate");
+                    let memory_stats = bench::compute_stats(
+                        &replicates.iter().map(|m| m.memory as f64).collect::<Vec<_>>()
+                    ).expect("at least one replicate");
+
                     tsv_writer_msa.write_record([
                         &dataset,
                         algo.to_str(),
-                        &measured.runtime.to_string(),
-                        &measured.memory_initial.map_or(String::default(), |v| v.to_string()),
-                        &measured.memory_total.map_or(String::default(), |v| v.to_string()),
-                        &measured.memory.to_string(),
-                        &measured.time_start.to_string(),
-                        &measured.time_end.to_string()
+                        &replicates.len().to_string(),
+                        &runtime_stats.mean.to_string(),
+                        &runtime_stats.median.to_string(),
+                        &runtime_stats.stddev.to_string(),
+                        &runtime_stats.mad.to_string(),
+                        &runtime_stats.min.to_string(),
+                        &runtime_stats.max.to_string(),
+                        &memory_stats.mean.to_string(),
+                        &memory_stats.median.to_string(),
+                        &memory_stats.stddev.to_string(),
+                        &memory_stats.mad.to_string(),
+                        &memory_stats.min.to_string(),
+                        &memory_stats.max.to_string(),
                     ])?;
                 },
-                JobResult::Finished(core) => {
+                JobResult::Finished(core, algorithm, benchmark, dataset_name) => {
                     tsv_writer_single.flush()?;
                     tsv_writer_msa.flush()?;
 
-                    if let Some(((algorithm, benchmark, dataset), job_tx)) = job_iter.next() {
+                    checkpoint.mark_completed((algorithm, benchmark, dataset_name), &bench_args.output_dir)?;
+                    in_flight -= 1;
+
+                    if let Some((algorithm, benchmark, dataset)) = job_iter.next() {
                         run_job(
                             scope, &proc_exe, &bench_args.datasets_dir, &bench_args.output_dir,
                             *algorithm, *benchmark, dataset, core.map(|v| CoreId { id: v }),
-                            job_tx
+                            bench_args.verify, bench_args.samples, bench_args.warmup,
+                            bench_args.sample_interval_ms, &bench_args.profilers, tx.clone()
                         );
+                        in_flight += 1;
                     }
                 },
-                JobResult::Error => {
-                    return Err(POABenchError::WorkerError.into());
+                JobResult::Error(core, algorithm, benchmark, dataset_name) => {
+                    eprintln!(
+                        "Job failed: algorithm={:?} benchmark={:?} dataset={}",
+                        algorithm, benchmark, dataset_name
+                    );
+
+                    let key = (algorithm, benchmark, dataset_name);
+                    let attempt = attempts.entry(key.clone()).or_insert(0);
+                    *attempt += 1;
+                    in_flight -= 1;
+
+                    if *attempt <= bench_args.retries {
+                        eprintln!("Retrying ({}/{}): {:?}", attempt, bench_args.retries, key);
+
+                        if let Some(&dataset) = dataset_lookup.get(key.2.as_str()) {
+                            run_job(
+                                scope, &proc_exe, &bench_args.datasets_dir, &bench_args.output_dir,
+                                key.0, key.1, dataset, core.map(|v| CoreId { id: v }),
+                                bench_args.verify, bench_args.samples, bench_args.warmup,
+                                bench_args.sample_interval_ms, &bench_args.profilers, tx.clone()
+                            );
+                            in_flight += 1;
+                        }
+                    } else {
+                        eprintln!("Giving up on job after {} attempt(s): {:?}", attempt, key);
+                        checkpoint.mark_failed(key, &bench_args.output_dir)?;
+
+                        if let Some((algorithm, benchmark, dataset)) = job_iter.next() {
+                            run_job(
+                                scope, &proc_exe, &bench_args.datasets_dir, &bench_args.output_dir,
+                                *algorithm, *benchmark, dataset, core.map(|v| CoreId { id: v }),
+                                bench_args.verify, bench_args.samples, bench_args.warmup,
+                                bench_args.sample_interval_ms, &bench_args.profilers, tx.clone()
+                            );
+                            in_flight += 1;
+                        }
+                    }
                 }
             }
         }
@@ -446,6 +827,7 @@ fn bench(bench_args: BenchArgs) -> Result<()> {
     })?;
 
     tsv_writer_single.flush()?;
+    run_manifest.finish(&bench_args.output_dir)?;
 
     Ok(())
 }
@@ -456,6 +838,7 @@ fn main() -> Result<(), Box<dyn Error>> {
     match args.command {
         Command::Bench(bench_args) => bench(bench_args)?,
         Command::Worker(worker_args) => worker::main(worker_args)?,
+        Command::Compare(compare_args) => compare::main(compare_args)?,
     }
 
     Ok(())
@@ -0,0 +1,125 @@
+//! Captures run-provenance metadata before `bench` launches any jobs, so two result sets -
+//! potentially produced on different machines, or days apart - can be diffed to explain why
+//! they disagree.
+
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::path::Path;
+use std::process::Command;
+
+use serde::Serialize;
+
+use crate::bench::{self, SystemInfo};
+use crate::dataset::Dataset;
+use crate::errors::POABenchError;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DatasetProvenance {
+    pub name: String,
+    /// Hash of `meta.toml`'s raw contents, so a config edit between two runs is visible even if
+    /// the dataset name didn't change. `None` if the file couldn't be read.
+    pub config_hash: Option<String>,
+    pub align_set_bytes: Option<u64>,
+    pub graph_set_bytes: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RunManifest {
+    pub poasta_version: String,
+    pub spoa_version: String,
+    pub spoa_build_graph_args: Vec<String>,
+    pub system: SystemInfo,
+    pub orchestrator_core: usize,
+    pub worker_cores: Vec<usize>,
+    pub thread_count: usize,
+    pub datasets: Vec<DatasetProvenance>,
+    pub start_time: chrono::DateTime<chrono::Utc>,
+    pub end_time: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+impl RunManifest {
+    /// Gathers everything that's known up front, i.e. before any job has been dispatched.
+    /// `end_time` is left unset until `finish` is called once the whole sweep completes.
+    pub fn capture(
+        datasets_dir: &Path, datasets: &[Dataset],
+        orchestrator_core: usize, worker_cores: &[usize], thread_count: usize,
+    ) -> Self {
+        RunManifest {
+            poasta_version: poasta_version(),
+            spoa_version: spoa_version(),
+            spoa_build_graph_args: crate::SPOA_BUILD_GRAPH_ARGS.iter().map(|v| v.to_string()).collect(),
+            system: bench::system_info(),
+            orchestrator_core,
+            worker_cores: worker_cores.to_vec(),
+            thread_count,
+            datasets: datasets.iter().map(|d| dataset_provenance(datasets_dir, d)).collect(),
+            start_time: chrono::Utc::now(),
+            end_time: None,
+        }
+    }
+
+    /// Writes (or overwrites) `output_dir/run_manifest.json`.
+    pub fn write(&self, output_dir: &Path) -> Result<(), POABenchError> {
+        let contents = serde_json::to_string_pretty(self)?;
+        std::fs::write(output_dir.join("run_manifest.json"), contents)?;
+        Ok(())
+    }
+
+    /// Stamps `end_time` and rewrites the manifest, once the whole sweep has finished.
+    pub fn finish(&mut self, output_dir: &Path) -> Result<(), POABenchError> {
+        self.end_time = Some(chrono::Utc::now());
+        self.write(output_dir)
+    }
+}
+
+fn dataset_provenance(datasets_dir: &Path, dataset: &Dataset) -> DatasetProvenance {
+    let dataset_dir = datasets_dir.join(dataset.name());
+    let config_hash = std::fs::read(dataset_dir.join("meta.toml")).ok()
+        .map(|contents| {
+            let mut hasher = DefaultHasher::new();
+            contents.hash(&mut hasher);
+            format!("{:016x}", hasher.finish())
+        });
+
+    DatasetProvenance {
+        name: dataset.name().to_string(),
+        config_hash,
+        align_set_bytes: std::fs::metadata(dataset.align_sequences_fname()).ok().map(|m| m.len()),
+        graph_set_bytes: dataset.graph_sequences_fname()
+            .and_then(|fname| std::fs::metadata(fname).ok())
+            .map(|m| m.len()),
+    }
+}
+
+/// Resolved version of the `poasta` crate, read from the `Cargo.lock` in the current working
+/// directory since `poasta` is a library with no CLI `--version` of its own. This is the linked
+/// version only when `bench` runs from the workspace that built it; "unknown" if no such
+/// `Cargo.lock` (or no `poasta` entry in it) is found.
+fn poasta_version() -> String {
+    cargo_lock_version("poasta").unwrap_or_else(|| String::from("unknown"))
+}
+
+/// Version of the `spoa` CLI tool found on `$PATH`, used to build each dataset's graph.
+fn spoa_version() -> String {
+    Command::new("spoa")
+        .arg("--version")
+        .output()
+        .ok()
+        .filter(|out| out.status.success())
+        .and_then(|out| String::from_utf8(out.stdout).ok())
+        .map(|s| s.trim().to_string())
+        .unwrap_or_else(|| String::from("unknown"))
+}
+
+/// Looks up `package`'s resolved version in `Cargo.lock` in the current working directory.
+fn cargo_lock_version(package: &str) -> Option<String> {
+    let lockfile = std::fs::read_to_string("Cargo.lock").ok()?;
+    let parsed: toml::Table = lockfile.parse().ok()?;
+    let packages = parsed.get("package")?.as_array()?;
+
+    packages.iter()
+        .find(|p| p.get("name").and_then(|n| n.as_str()) == Some(package))
+        .and_then(|p| p.get("version"))
+        .and_then(|v| v.as_str())
+        .map(String::from)
+}
@@ -0,0 +1,188 @@
+//! Optional profiler/resource-monitor attachment for worker child processes, mirroring how
+//! windsock's `--profilers` flag lets a benchmark runner attach `perf`/`samply`/a resource
+//! poller to the process under test instead of only reporting its own timing.
+
+use std::fs::File;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use clap::ValueEnum;
+use serde::Serialize;
+
+use crate::errors::POABenchError;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Profiler {
+    /// CPU stack samples via `perf record`, dumped as `perf script` text (not rendered to a
+    /// flamegraph).
+    Perf,
+    /// CPU profile via `samply record`, saved as samply's JSON profile.
+    Samply,
+    /// RSS/CPU time series sampled from `/proc/<pid>/stat`, no external tool required.
+    SysMonitor,
+}
+
+impl Profiler {
+    pub fn to_str(&self) -> &str {
+        match self {
+            Self::Perf => "perf",
+            Self::Samply => "samply",
+            Self::SysMonitor => "sys_monitor",
+        }
+    }
+
+    /// File extension of the artifact this profiler produces.
+    fn extension(&self) -> &str {
+        match self {
+            Self::Perf => "txt",
+            Self::Samply | Self::SysMonitor => "json",
+        }
+    }
+}
+
+/// A profiler attached to a running worker process. Must be handed to `finish` once the worker
+/// has exited, so the spawned profiler process (if any) is waited on and its artifact written.
+pub enum ProfilerHandle {
+    Child(Child, Profiler, PathBuf),
+    SysMonitor {
+        stop: Arc<AtomicBool>,
+        handle: JoinHandle<Vec<SysMonitorSample>>,
+        artifact_path: PathBuf,
+    },
+}
+
+#[derive(Debug, Serialize)]
+pub struct SysMonitorSample {
+    pub elapsed_ms: u64,
+    pub rss_kb: u64,
+    pub utime_ticks: u64,
+    pub stime_ticks: u64,
+}
+
+/// Attaches `profiler` to the already-running worker process `pid`, arranging for its artifact
+/// to eventually be written to `output_dir/<dataset>/<algorithm>.<profiler>.{txt,json}`.
+pub fn attach(
+    profiler: Profiler, pid: u32, output_dir: &Path, dataset: &str, algorithm: &str
+) -> Result<ProfilerHandle, POABenchError> {
+    let dataset_dir = output_dir.join(dataset);
+    std::fs::create_dir_all(&dataset_dir)?;
+    let artifact_path = dataset_dir.join(
+        format!("{algorithm}.{}.{}", profiler.to_str(), profiler.extension())
+    );
+
+    match profiler {
+        Profiler::Perf => {
+            let child = Command::new("perf")
+                .args(["record", "-g", "-o"])
+                .arg(artifact_path.with_extension("perf.data"))
+                .arg("-p")
+                .arg(pid.to_string())
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .spawn()?;
+
+            Ok(ProfilerHandle::Child(child, profiler, artifact_path))
+        },
+        Profiler::Samply => {
+            let child = Command::new("samply")
+                .args(["record", "--save-only", "-o"])
+                .arg(&artifact_path)
+                .arg("-p")
+                .arg(pid.to_string())
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .spawn()?;
+
+            Ok(ProfilerHandle::Child(child, profiler, artifact_path))
+        },
+        Profiler::SysMonitor => {
+            let stop = Arc::new(AtomicBool::new(false));
+            let stop_for_thread = Arc::clone(&stop);
+
+            let handle = thread::spawn(move || {
+                let start = Instant::now();
+                let mut samples = Vec::new();
+
+                while !stop_for_thread.load(Ordering::Relaxed) {
+                    match sample_proc(pid, start.elapsed()) {
+                        Some(sample) => samples.push(sample),
+                        None => break,
+                    }
+
+                    thread::sleep(Duration::from_millis(100));
+                }
+
+                samples
+            });
+
+            Ok(ProfilerHandle::SysMonitor { stop, handle, artifact_path })
+        },
+    }
+}
+
+/// Stops the profiler (if it's a background thread) or waits for it (if it's a spawned
+/// process), then writes its collected artifact to disk. Call once the profiled worker process
+/// has exited.
+pub fn finish(handle: ProfilerHandle) -> Result<(), POABenchError> {
+    match handle {
+        ProfilerHandle::Child(mut child, profiler, artifact_path) => {
+            child.wait()?;
+
+            if profiler == Profiler::Perf {
+                // `perf record` only produces the raw `perf.data`; fold it into a flat stack
+                // trace via `perf script` here so the artifact is at least ready for a
+                // flamegraph tool to render, without requiring one to be installed.
+                let script = Command::new("perf")
+                    .arg("script")
+                    .arg("-i")
+                    .arg(artifact_path.with_extension("perf.data"))
+                    .output()?;
+
+                if script.status.success() {
+                    File::create(&artifact_path)?.write_all(&script.stdout)?;
+                }
+            }
+
+            Ok(())
+        },
+        ProfilerHandle::SysMonitor { stop, handle, artifact_path } => {
+            stop.store(true, Ordering::Relaxed);
+            let samples = handle.join().expect("sys_monitor thread panicked");
+
+            let file = File::create(&artifact_path)?;
+            serde_json::to_writer(file, &samples)?;
+
+            Ok(())
+        },
+    }
+}
+
+/// Reads `/proc/<pid>/stat` for RSS and CPU ticks, returning `None` once the process has exited
+/// (or the read otherwise fails).
+fn sample_proc(pid: u32, elapsed: Duration) -> Option<SysMonitorSample> {
+    let contents = std::fs::read_to_string(format!("/proc/{pid}/stat")).ok()?;
+    // Field 2 (comm) may itself contain spaces/parens, so split on the last ')' instead of
+    // naively splitting on whitespace from the start.
+    let after_comm = contents.rsplit_once(')')?.1;
+    let fields: Vec<&str> = after_comm.split_whitespace().collect();
+
+    // Indices below are 0-based counting from the field right after `comm`, i.e. state (field 3
+    // in `man 5 proc`). utime/stime are fields 14/15, rss (in pages) is field 24.
+    let utime_ticks = fields.get(11)?.parse().ok()?;
+    let stime_ticks = fields.get(12)?.parse().ok()?;
+    let rss_pages: u64 = fields.get(21)?.parse().ok()?;
+    let page_size_kb = unsafe { libc::sysconf(libc::_SC_PAGESIZE) } as u64 / 1024;
+
+    Some(SysMonitorSample {
+        elapsed_ms: elapsed.as_millis() as u64,
+        rss_kb: rss_pages * page_size_kb,
+        utime_ticks,
+        stime_ticks,
+    })
+}
@@ -0,0 +1,174 @@
+//! Correctness checks for the `--verify` benchmark mode.
+//!
+//! Two independent checks live here: replaying a single POASTA alignment's own traceback
+//! through the `Affine` cost model to make sure the reported score is actually what the
+//! traceback costs (catches a broken cost accounting hiding behind a plausible-looking
+//! number), and comparing scores for the same sequence across aligner backends.
+
+use poasta::aligner::astar::AlignableGraph;
+use poasta::aligner::alignment::{Alignment, AlignedPair};
+use poasta::aligner::cost_models::affine::Affine;
+
+use crate::errors::POABenchError;
+use crate::jobs::Algorithm;
+
+/// Recomputes the cost of `alignment` by walking it pair by pair and applying `cost_model`,
+/// and returns an error describing the mismatch if it disagrees with `reported_score`.
+///
+/// A run of consecutive insertions or deletions is charged one gap open plus one gap extend
+/// per gapped position, matching the affine gap penalty `Affine::new(mismatch, gap_open,
+/// gap_extend)` is constructed with elsewhere in this crate.
+pub fn verify_poasta_traceback<G: AlignableGraph>(
+    graph: &G,
+    query: &[u8],
+    alignment: &Alignment<G::NodeIndex>,
+    cost_model: &Affine<i32>,
+    reported_score: usize,
+) -> Result<(), POABenchError> {
+    let mut cost: i64 = 0;
+    let mut in_gap = false;
+
+    for pair in alignment {
+        match (pair.rpos, pair.qpos) {
+            (Some(rpos), Some(qpos)) => {
+                in_gap = false;
+
+                if graph.get_symbol(rpos) != query[qpos] {
+                    cost += cost_model.mismatch() as i64;
+                }
+            },
+            (None, Some(_)) | (Some(_), None) => {
+                if !in_gap {
+                    cost += cost_model.gap_open() as i64;
+                }
+                cost += cost_model.gap_extend() as i64;
+                in_gap = true;
+            },
+            (None, None) => {},
+        }
+    }
+
+    if cost as usize != reported_score {
+        return Err(POABenchError::VerificationError(format!(
+            "POASTA traceback cost {} does not match reported score {}", cost, reported_score
+        )));
+    }
+
+    Ok(())
+}
+
+/// Per-sequence scores gathered from the different aligner backends run on the same dataset,
+/// used to flag disagreements beyond the configured tolerance.
+pub type ScoreComparison = Vec<(Algorithm, usize)>;
+
+/// Backends whose reported scores are on a directly comparable cost scale. POASTA's `Affine`
+/// model charges `gap_open + gap_extend * L` for a gap run of length `L` (see
+/// `verify_poasta_traceback` above), and SPOA's affine engine is deliberately configured with a
+/// gap-open penalty of `gap_open + gap_extend` (`aligner.rs`'s `new_affine(.., -8, -2)`, i.e.
+/// POASTA's `6 + 2`) so that its own "open once, extend for the rest" convention produces the
+/// same total for the same gap run. abPOA's `gap_affine_penalties` are passed the same raw
+/// `gap_open`/`gap_extend` as POASTA without that adjustment, and abPOA's internal gap-cost
+/// convention isn't confirmed to match -- comparing its raw score against the others under a flat
+/// tolerance risks flagging a cost-model mismatch as a benchmark regression. Its score is still
+/// recorded and reported, just excluded from this check.
+const COMPARABLE_COST_MODEL: &[Algorithm] = &[Algorithm::POASTA, Algorithm::SPOA];
+
+/// Returns `Some(error message)` when the spread between the lowest and highest score among
+/// `scores`' entries that share a [`COMPARABLE_COST_MODEL`] exceeds `tolerance` (a fraction of
+/// the lowest score). `None` both when everything agrees and when fewer than two backends in
+/// `scores` have a comparable cost model, since there's then nothing meaningful to compare.
+pub fn check_score_agreement(dataset: &str, seq_name: &str, scores: &ScoreComparison, tolerance: f64) -> Option<String> {
+    let comparable: ScoreComparison = scores.iter()
+        .copied()
+        .filter(|(algo, _)| COMPARABLE_COST_MODEL.contains(algo))
+        .collect();
+
+    if comparable.len() < 2 {
+        return None;
+    }
+
+    let min = comparable.iter().map(|(_, score)| *score).min()?;
+    let max = comparable.iter().map(|(_, score)| *score).max()?;
+
+    if min == 0 {
+        return if max == 0 { None } else {
+            Some(format!(
+                "Score disagreement for {}/{}: {:?}", dataset, seq_name, comparable
+            ))
+        };
+    }
+
+    let spread = (max - min) as f64 / min as f64;
+    if spread > tolerance {
+        Some(format!(
+            "Score disagreement for {}/{} beyond tolerance {:.1}%: {:?}",
+            dataset, seq_name, tolerance * 100.0, comparable
+        ))
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use poasta::aligner::astar::heuristic::Dijkstra;
+    use poasta::aligner::{AlignmentMode, GraphAligner, PoastaAligner};
+    use poasta::graph::poa::POASeqGraph;
+
+    use super::*;
+
+    /// Builds a graph from a single reference and aligns a query two bases shorter, so the
+    /// resulting traceback contains a gap run -- this is what would have caught the gap-cost
+    /// bug where the first gapped position didn't charge `gap_extend`.
+    fn align_with_gap() -> (POASeqGraph<u32>, Vec<u8>, Affine<i32>, usize) {
+        let mut graph = POASeqGraph::<u32>::new();
+        let weights = vec![1; 6];
+        graph.add_aligned_sequence("ref", b"ACGTAC", &weights, None).unwrap();
+
+        let cost_model = Affine::new(4, 6, 2);
+        let aligner = PoastaAligner::<Dijkstra, i32, u32, _, _>::new(cost_model);
+
+        let query = b"ACAC".to_vec();
+        let result = aligner.align(&graph, &query, AlignmentMode::Global).unwrap();
+
+        (graph, query, cost_model, result.score.as_usize())
+    }
+
+    #[test]
+    fn verify_poasta_traceback_accepts_correct_gap_cost() {
+        let (graph, query, cost_model, score) = align_with_gap();
+        let aligner = PoastaAligner::<Dijkstra, i32, u32, _, _>::new(cost_model);
+        let result = aligner.align(&graph, &query, AlignmentMode::Global).unwrap();
+
+        assert!(verify_poasta_traceback(&graph, &query, &result.alignment, &cost_model, score).is_ok());
+    }
+
+    #[test]
+    fn verify_poasta_traceback_rejects_wrong_score() {
+        let (graph, query, cost_model, score) = align_with_gap();
+        let aligner = PoastaAligner::<Dijkstra, i32, u32, _, _>::new(cost_model);
+        let result = aligner.align(&graph, &query, AlignmentMode::Global).unwrap();
+
+        assert!(verify_poasta_traceback(&graph, &query, &result.alignment, &cost_model, score + 1).is_err());
+    }
+
+    #[test]
+    fn check_score_agreement_within_tolerance_is_none() {
+        let scores: ScoreComparison = vec![(Algorithm::POASTA, 100), (Algorithm::SPOA, 102)];
+        assert!(check_score_agreement("dataset", "seq1", &scores, 0.05).is_none());
+    }
+
+    #[test]
+    fn check_score_agreement_beyond_tolerance_is_some() {
+        let scores: ScoreComparison = vec![(Algorithm::POASTA, 100), (Algorithm::SPOA, 200)];
+        assert!(check_score_agreement("dataset", "seq1", &scores, 0.05).is_some());
+    }
+
+    #[test]
+    fn check_score_agreement_ignores_abpoa_divergence() {
+        // abPOA's score isn't on a confirmed-comparable cost scale (see `COMPARABLE_COST_MODEL`),
+        // so it shouldn't be able to trip the check even when wildly different from POASTA's.
+        let scores: ScoreComparison = vec![(Algorithm::POASTA, 100), (Algorithm::abPOA, 1000)];
+        assert!(check_score_agreement("dataset", "seq1", &scores, 0.05).is_none());
+    }
+}